@@ -1,7 +1,19 @@
 use std::convert::TryInto;
+use std::fmt;
+use std::num::ParseIntError;
 
-use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
-use rand::{thread_rng, Rng};
+use rand::Rng;
+
+/// Number of columns of the classic board.
+pub const WIDTH: usize = 7;
+/// Number of rows of the classic board.
+pub const HEIGHT: usize = 6;
+/// Number of aligned tokens needed to win on the classic board.
+pub const CONNECT: usize = 4;
+/// Number of bits of a bitboard, the columns and their extra bit must fit in it.
+pub const MAX_BITS: usize = 128;
+/// Number of columns of the widest board, each column takes at least two bits.
+pub const MAX_COLS: usize = MAX_BITS / 2;
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Token {
@@ -10,260 +22,467 @@ pub enum Token {
     Empty,
 }
 
-#[derive(Debug)]
-pub struct Board([[Token; 7]; 6], Vec<i8>);
-
-impl Board {
-    pub fn new() -> Board {
-        Board(
-            [[Token::Empty; 7]; 6],
-            vec![
-                0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4,
-                4, 4, 5, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 6,
-            ],
-        )
+impl Token {
+    // The token of the other player, `Empty` has no opponent
+    pub fn opponent(self) -> Token {
+        match self {
+            Token::Red => Token::Yellow,
+            Token::Yellow => Token::Red,
+            Token::Empty => Token::Empty,
+        }
     }
+}
 
-    pub fn display(&self) {
-        let separation_color = Color::Rgb {
-            r: 0,
-            g: 100,
-            b: 255,
-        };
+// Direction of a line of tokens, as seen on the displayed board
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Vertical,
+    // from the top left to the bottom right
+    DiagonalDown,
+    Horizontal,
+    // from the bottom left to the top right
+    DiagonalUp,
+}
 
-        // First row to display column numbers
-        println!(
-            "\n\t{}                             {}",
-            SetBackgroundColor(separation_color),
-            ResetColor
-        );
-        print!(
-            "column :{} {}",
-            SetBackgroundColor(separation_color),
-            ResetColor
-        );
-        for i in 1..=7 {
-            print!(
-                "{}{} {} {} {}",
-                SetBackgroundColor(Color::DarkCyan),
-                SetForegroundColor(Color::Rgb {
-                    r: 255,
-                    g: 255,
-                    b: 0
-                }),
-                i,
-                SetBackgroundColor(separation_color),
-                ResetColor
-            );
+impl Direction {
+    // In the same order as the shifts returned by `Board::directions`
+    pub const ALL: [Direction; 4] = [
+        Direction::Vertical,
+        Direction::DiagonalDown,
+        Direction::Horizontal,
+        Direction::DiagonalUp,
+    ];
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Vertical => write!(f, "vertical"),
+            Direction::DiagonalDown | Direction::DiagonalUp => write!(f, "diagonal"),
+            Direction::Horizontal => write!(f, "horizontal"),
         }
-        println!(
-            "\n\t{}                             {}",
-            SetBackgroundColor(separation_color),
-            ResetColor
-        );
+    }
+}
 
-        // Displays the body of the board with different background color
-        for row in self.0.iter() {
-            print!("\t{} {}", SetBackgroundColor(separation_color), ResetColor);
-            for cell in row {
-                match cell {
-                    Token::Empty => print!("{}   {}", SetBackgroundColor(Color::White), ResetColor),
-                    Token::Yellow => print!(
-                        "{}   {}",
-                        SetBackgroundColor(Color::Rgb {
-                            r: 255,
-                            g: 255,
-                            b: 50
-                        }),
-                        ResetColor
-                    ),
-                    Token::Red => print!(
-                        "{}   {}",
-                        SetBackgroundColor(Color::Rgb { r: 255, g: 0, b: 0 }),
-                        ResetColor
-                    ),
-                };
-                print!("{} {}", SetBackgroundColor(separation_color), ResetColor);
-            }
-            println!(
-                "\n\t{}                             {}",
-                SetBackgroundColor(separation_color),
-                ResetColor
-            );
+#[derive(Clone, PartialEq, Debug)]
+pub struct WinningLine {
+    pub token: Token,
+    pub direction: Direction,
+    // Cells of the line as `(x, y)`, `x` being the row counted from the top and
+    // `y` the column, in the order of the direction
+    pub cells: Vec<(usize, usize)>,
+}
+
+// Reason why a move was rejected
+#[derive(Clone, PartialEq, Debug)]
+pub enum MoveError {
+    // The column is not on the board
+    OutOfRange,
+    // The column has no free cell left
+    ColumnFull,
+    // A player already won or the board is full
+    GameOver,
+    // The token is not the one of the player about to play
+    WrongTurn,
+    // The input is not a column number
+    Parse(ParseIntError),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::OutOfRange => write!(f, "this column is not on the board"),
+            MoveError::ColumnFull => write!(f, "this column is already full"),
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::WrongTurn => write!(f, "it is not the turn of this player"),
+            MoveError::Parse(err) => write!(f, "this is not a column number ({})", err),
         }
     }
+}
 
-    pub fn is_full(&self) -> bool {
-        for row in self.0.iter() {
-            for cell in row {
-                if cell == &Token::Empty {
-                    return false;
-                }
-            }
+impl std::error::Error for MoveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MoveError::Parse(err) => Some(err),
+            _ => None,
         }
-        true
     }
+}
 
-    fn check_cell(&self, x: usize, y: usize, token: &Token) -> bool {
-        match self.0.get(x) {
-            None => false,
-            Some(row) => match row.get(y) {
-                None => false,
-                Some(cell) => token == cell,
-            },
+impl From<ParseIntError> for MoveError {
+    fn from(err: ParseIntError) -> MoveError {
+        MoveError::Parse(err)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SizeError {
+    // The board has no column or no row
+    Empty,
+    // The columns with their extra bit do not fit in a bitboard
+    TooLarge,
+    // The line to connect is shorter than 2 or longer than the board
+    InvalidConnect,
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SizeError::Empty => write!(f, "the board needs at least one column and one row"),
+            SizeError::TooLarge => write!(
+                f,
+                "the board is too large, columns * (rows + 1) must not exceed {}",
+                MAX_BITS
+            ),
+            SizeError::InvalidConnect => write!(
+                f,
+                "the number of tokens to connect must be at least 2 and fit in the board"
+            ),
         }
     }
-    fn check_row(&self, token: &Token) -> bool {
-        let mut count = 4;
-        for x in 0..6 {
-            for y in 0..4 {
-                count = if self.check_cell(x, y, token) {
-                    count - 1
-                } else {
-                    4
-                };
-                if count == 0 {
-                    return true;
-                }
-            }
+}
+
+impl std::error::Error for SizeError {}
+
+// The cells of the board are stored in two bitboards, one per player, where
+// the cell at `row` (counted from the bottom) of column `col` is the bit
+// `col * (rows + 1) + row`. Each column uses one extra bit on top of its cells
+// so that shifts never carry a token from the top of a column into the bottom
+// of the next one. The height of each column gives the next free row.
+// The columns played are kept in order, the undone moves can be played again
+// until a new move is played. The first line completed is remembered so only
+// the token just played is checked after each move.
+#[derive(Clone, Debug)]
+pub struct Board {
+    cols: usize,
+    rows: usize,
+    connect: usize,
+    red: u128,
+    yellow: u128,
+    winner: Token,
+    heights: [u8; MAX_COLS],
+    history: Vec<usize>,
+    undone: Vec<(usize, Token)>,
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
+    }
+}
+
+impl Board {
+    pub fn new() -> Board {
+        Board::with_size(WIDTH, HEIGHT, CONNECT).unwrap()
+    }
+
+    pub fn with_size(cols: usize, rows: usize, connect: usize) -> Result<Board, SizeError> {
+        if cols == 0 || rows == 0 {
+            return Err(SizeError::Empty);
         }
-        false
-    }
-    fn check_column(&self, token: &Token) -> bool {
-        let mut count = 4;
-        for y in 0..7 {
-            for x in 0..3 {
-                count = if self.check_cell(x, y, token) {
-                    count - 1
-                } else {
-                    4
-                };
-                if count == 0 {
-                    return true;
-                }
-            }
+        if cols * (rows + 1) > MAX_BITS {
+            return Err(SizeError::TooLarge);
         }
-        false
-    }
-    fn check_diagonal(&self, token: &Token) -> bool {
-        // LEFT => RIGHT
-        for row in 0..3 {
-            let mut count = 4;
-            let mut x = row;
-            for y in 0..7 {
-                count = if self.check_cell(x, y, token) {
-                    count - 1
-                } else {
-                    4
-                };
-                if count == 0 {
-                    return true;
-                }
-                x += 1;
-            }
+        if connect < 2 || connect > cols.max(rows) {
+            return Err(SizeError::InvalidConnect);
         }
+        Ok(Board {
+            cols,
+            rows,
+            connect,
+            red: 0,
+            yellow: 0,
+            winner: Token::Empty,
+            heights: [0; MAX_COLS],
+            history: Vec::new(),
+            undone: Vec::new(),
+        })
+    }
 
-        for col in 1..4 {
-            let mut count = 4;
-            let mut y = col;
-            for x in 0..6 {
-                count = if self.check_cell(x, y, token) {
-                    count - 1
-                } else {
-                    4
-                };
-                if count == 0 {
-                    return true;
-                }
-                y += 1;
-            }
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn connect(&self) -> usize {
+        self.connect
+    }
+
+    // Number of bits used by a column in the bitboards
+    pub fn column_bits(&self) -> usize {
+        self.rows + 1
+    }
+
+    fn bit(&self, row: usize, col: usize) -> u128 {
+        1 << (col * self.column_bits() + row)
+    }
+
+    // Returns the token at the given cell, `x` being the row counted from the top
+    // and `y` the column, as it is displayed.
+    pub fn cell(&self, x: usize, y: usize) -> Option<Token> {
+        if x >= self.rows || y >= self.cols {
+            return None;
         }
+        let bit = self.bit(self.rows - 1 - x, y);
+        Some(if self.red & bit != 0 {
+            Token::Red
+        } else if self.yellow & bit != 0 {
+            Token::Yellow
+        } else {
+            Token::Empty
+        })
+    }
 
-        // RIGHT => LEFT
-        for row in 0..3 {
-            let mut count = 4;
-            let mut x = row;
-            for y in (1..7).rev() {
-                count = if self.check_cell(x, y, token) {
-                    count - 1
+    // Columns sorted from the center to the edges, central columns belong to
+    // more lines so the search engines explore them first
+    pub fn center_order(&self) -> Vec<usize> {
+        (0..self.cols)
+            .map(|i| {
+                if i % 2 == 0 {
+                    self.cols / 2 + i / 2
                 } else {
-                    4
-                };
-                if count == 0 {
-                    return true;
+                    self.cols / 2 - i.div_ceil(2)
                 }
-                x += 1;
-            }
+            })
+            .collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.heights[..self.cols].iter().all(|&height| height as usize == self.rows)
+    }
+
+    #[cfg(test)]
+    fn check_cell(&self, x: usize, y: usize, token: &Token) -> bool {
+        self.cell(x, y) == Some(*token)
+    }
+
+    // Shifts used to move every token one cell along a line: vertical,
+    // diagonal going down, horizontal and diagonal going up
+    pub fn directions(&self) -> [usize; 4] {
+        let bits = self.column_bits();
+        [1, bits - 1, bits, bits + 1]
+    }
+
+    // Shifting the bitboard by `shift` moves every token one cell along a
+    // direction. The bits left after `line & (line >> shift)` are the starts of
+    // runs one token longer, the length of the runs doubles at each step until
+    // only the first cells of `connect` aligned tokens remain.
+    fn line_starts(&self, mask: u128, shift: usize) -> u128 {
+        let (mut line, mut len) = (mask, 1);
+        while len < self.connect && line != 0 {
+            let step = len.min(self.connect - len);
+            line &= line.checked_shr((step * shift) as u32).unwrap_or(0);
+            len += step;
         }
+        line
+    }
 
-        for col in 3..6 {
-            let mut count = 4;
-            let mut y = col;
-            for x in 0..6 {
-                count = if self.check_cell(x, y, token) {
-                    count - 1
-                } else {
-                    4
-                };
-                if count == 0 {
-                    return true;
-                }
-                if y == 0 {
-                    break;
-                }
-                y -= 1;
+    fn has_line(&self, mask: u128) -> bool {
+        // the usual boards fit in 64 bits where four aligned tokens leave a
+        // bit set after a shift of one cell and one of two cells
+        if self.connect == 4 && self.cols * self.column_bits() <= 64 {
+            let mask = mask as u64;
+            return self.directions().iter().any(|&shift| {
+                let pairs = mask & mask.checked_shr(shift as u32).unwrap_or(0);
+                pairs & pairs.checked_shr(2 * shift as u32).unwrap_or(0) != 0
+            });
+        }
+        self.directions()
+            .iter()
+            .any(|&shift| self.line_starts(mask, shift) != 0)
+    }
+
+    // Returns the first line completed, if any
+    pub fn winning_line(&self) -> Option<WinningLine> {
+        let mask = match self.winner {
+            Token::Red => self.red,
+            Token::Yellow => self.yellow,
+            Token::Empty => return None,
+        };
+        for (direction, shift) in Direction::ALL.iter().zip(self.directions()) {
+            let starts = self.line_starts(mask, shift);
+            if starts != 0 {
+                let start = starts.trailing_zeros() as usize;
+                let cells = (0..self.connect)
+                    .map(|k| {
+                        let bit = start + k * shift;
+                        let (col, row) = (bit / self.column_bits(), bit % self.column_bits());
+                        (self.rows - 1 - row, col)
+                    })
+                    .collect();
+                return Some(WinningLine {
+                    token: self.winner,
+                    direction: *direction,
+                    cells,
+                });
             }
         }
-        false
+        None
+    }
+
+    // Number of tokens on the board
+    pub fn moves(&self) -> usize {
+        (self.red | self.yellow).count_ones() as usize
+    }
+
+    // Bitboards of the red and yellow tokens, see the layout described on `Board`
+    pub fn bitboards(&self) -> (u128, u128) {
+        (self.red, self.yellow)
     }
+
+    pub fn can_play(&self, col: usize) -> bool {
+        col < self.cols && (self.heights[col] as usize) < self.rows
+    }
+
     pub fn check_winner(&self) -> Token {
-        if self.check_row(&Token::Red)
-            || self.check_column(&Token::Red)
-            || self.check_diagonal(&Token::Red)
-            || self.check_diagonal(&Token::Red)
-        {
-            return Token::Red;
-        } else if self.check_row(&Token::Yellow)
-            || self.check_column(&Token::Yellow)
-            || self.check_diagonal(&Token::Yellow)
-            || self.check_diagonal(&Token::Yellow)
-        {
-            return Token::Yellow;
+        self.winner
+    }
+
+    fn find_winner(&self) -> Token {
+        if self.has_line(self.red) {
+            Token::Red
+        } else if self.has_line(self.yellow) {
+            Token::Yellow
+        } else {
+            Token::Empty
         }
-        Token::Empty
     }
 
-    pub fn player_stroke(&mut self, token: Token, col: i8) -> Option<bool> {
+    pub fn is_over(&self) -> bool {
+        self.winner != Token::Empty || self.is_full()
+    }
+
+    pub fn player_stroke(&mut self, token: Token, col: i8) -> Result<(), MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if token == Token::Empty {
+            return Err(MoveError::WrongTurn);
+        }
         match col.try_into() {
-            Ok(col) if col < 7 => {
-                if !self.check_cell(0, col, &Token::Empty) {
-                    Some(false)
-                } else {
-                    for row in (0..6).rev() {
-                        if self.check_cell(row, col, &Token::Empty) {
-                            self.0[row][col] = token;
-                            self.1
-                                .remove(self.1.iter().position(|&c| c == col as i8).unwrap());
-                            return Some(true);
-                        }
+            Ok(col) if col < self.cols => {
+                let height = self.heights[col] as usize;
+                if height == self.rows {
+                    return Err(MoveError::ColumnFull);
+                }
+                let bit = self.bit(height, col);
+                let mask = match token {
+                    Token::Red => {
+                        self.red |= bit;
+                        self.red
                     }
-                    None
+                    Token::Yellow => {
+                        self.yellow |= bit;
+                        self.yellow
+                    }
+                    Token::Empty => unreachable!(),
+                };
+                if self.has_line(mask) {
+                    self.winner = token;
                 }
+                self.heights[col] += 1;
+                self.history.push(col);
+                self.undone.clear();
+                Ok(())
             }
-            _ => None,
+            _ => Err(MoveError::OutOfRange),
         }
     }
-    pub fn random_stroke(&mut self, token: Token) -> Option<bool> {
-        match self.1.get(thread_rng().gen_range(0..self.1.len()) as usize) {
-            None => None,
-            Some(&col) => self.player_stroke(token, col),
+
+    // Columns played since the start of the game, in order
+    pub fn history(&self) -> &[usize] {
+        &self.history
+    }
+
+    // Column and color of the last token played
+    pub fn last_move(&self) -> Option<(usize, Token)> {
+        let &col = self.history.last()?;
+        let x = self.rows - self.heights[col] as usize;
+        self.cell(x, col).map(|token| (col, token))
+    }
+
+    // Removes the last token played, returns its column and its color
+    pub fn undo(&mut self) -> Option<(usize, Token)> {
+        let col = self.history.pop()?;
+        self.heights[col] -= 1;
+        let bit = self.bit(self.heights[col] as usize, col);
+        let token = if self.red & bit != 0 {
+            Token::Red
+        } else {
+            Token::Yellow
+        };
+        self.red &= !bit;
+        self.yellow &= !bit;
+        self.winner = self.find_winner();
+        self.undone.push((col, token));
+        Some((col, token))
+    }
+
+    // Plays again the last undone move, returns its column and its color
+    pub fn redo(&mut self) -> Option<(usize, Token)> {
+        let (col, token) = self.undone.pop()?;
+        let undone = std::mem::take(&mut self.undone);
+        let played = self.player_stroke(token, col as i8);
+        self.undone = undone;
+        played.ok().map(|_| (col, token))
+    }
+
+    // A random column that is not full, if any
+    pub fn random_column<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let mut playable = [0; MAX_COLS];
+        let mut len = 0;
+        for col in 0..self.cols {
+            if self.can_play(col) {
+                playable[len] = col as u8;
+                len += 1;
+            }
+        }
+        if len == 0 {
+            return None;
         }
+        Some(playable[rng.gen_range(0..len)] as usize)
+    }
+
+    // Plays the token in a random column that is not full, drawn from `rng`
+    pub fn random_stroke<R: Rng + ?Sized>(&mut self, token: Token, rng: &mut R) -> Result<(), MoveError> {
+        let col = self.random_column(rng).ok_or(MoveError::GameOver)?;
+        self.player_stroke(token, col as i8)
+    }
+
+    // Builds a board from its rows laid out as they are displayed, first row on
+    // top. The board has no history, the order of the moves is unknown.
+    pub fn from_rows(rows: &[Vec<Token>], connect: usize) -> Result<Board, SizeError> {
+        let cols = rows.first().map_or(0, Vec::len);
+        let mut board = Board::with_size(cols, rows.len(), connect)?;
+        for (x, row) in rows.iter().enumerate() {
+            for (y, cell) in row.iter().enumerate().take(cols) {
+                let bit = board.bit(rows.len() - 1 - x, y);
+                match cell {
+                    Token::Red => board.red |= bit,
+                    Token::Yellow => board.yellow |= bit,
+                    Token::Empty => continue,
+                }
+                board.heights[y] = board.heights[y].max((rows.len() - x) as u8);
+            }
+        }
+        board.winner = board.find_winner();
+        Ok(board)
+    }
+
+    #[cfg(test)]
+    fn from_grid<const C: usize, const R: usize>(grid: [[Token; C]; R], connect: usize) -> Board {
+        let rows: Vec<Vec<Token>> = grid.iter().map(|row| row.to_vec()).collect();
+        Board::from_rows(&rows, connect).unwrap()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Board, Token::*};
+    use super::{Board, Direction, MoveError, SizeError, Token::*, WinningLine};
+    use rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn empty_board_is_full() {
@@ -272,13 +491,13 @@ mod tests {
 
     #[test]
     fn full_board_is_full() {
-        let board = Board([[Red; 7]; 6], Vec::new());
+        let board = Board::from_grid([[Red; 7]; 6], 4);
         assert!(board.is_full())
     }
 
     #[test]
     fn check_row_winner() {
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty; 7],
@@ -287,10 +506,10 @@ mod tests {
                 [Empty; 7],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty; 7],
@@ -299,10 +518,10 @@ mod tests {
                 [Empty, Empty, Empty, Red, Red, Red, Red],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty; 7],
@@ -311,14 +530,14 @@ mod tests {
                 [Empty; 7],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
     }
 
     #[test]
     fn check_column_winner() {
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty, Empty, Red, Empty, Empty, Empty, Empty],
@@ -327,10 +546,10 @@ mod tests {
                 [Empty, Empty, Red, Empty, Empty, Empty, Empty],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty, Empty, Empty, Empty, Empty, Empty, Red],
                 [Empty, Empty, Empty, Empty, Empty, Empty, Red],
@@ -339,10 +558,10 @@ mod tests {
                 [Empty; 7],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty; 7],
@@ -351,14 +570,14 @@ mod tests {
                 [Empty, Empty, Empty, Empty, Yellow, Empty, Empty],
                 [Empty, Empty, Empty, Empty, Yellow, Empty, Empty],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Yellow);
     }
 
     #[test]
     fn check_diagonal_left_right() {
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty; 7],
@@ -367,10 +586,10 @@ mod tests {
                 [Empty, Empty, Red, Empty, Empty, Empty, Empty],
                 [Empty, Empty, Empty, Red, Empty, Empty, Empty],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty, Empty, Yellow, Empty, Empty, Empty, Empty],
@@ -379,10 +598,10 @@ mod tests {
                 [Empty, Empty, Empty, Empty, Empty, Yellow, Empty],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Yellow);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty, Empty, Empty, Red, Empty, Empty, Empty],
                 [Empty, Empty, Empty, Empty, Red, Empty, Empty],
@@ -391,14 +610,14 @@ mod tests {
                 [Empty; 7],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
     }
 
     #[test]
     fn check_diagonal_right_left() {
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty; 7],
                 [Empty; 7],
@@ -407,10 +626,10 @@ mod tests {
                 [Empty, Empty, Empty, Red, Empty, Empty, Empty],
                 [Empty, Empty, Red, Empty, Empty, Empty, Empty],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty, Empty, Empty, Yellow, Empty, Empty, Empty],
                 [Empty, Empty, Yellow, Empty, Empty, Empty, Empty],
@@ -419,10 +638,10 @@ mod tests {
                 [Empty; 7],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Yellow);
-        let board = Board(
+        let board = Board::from_grid(
             [
                 [Empty, Empty, Empty, Empty, Empty, Empty, Red],
                 [Empty, Empty, Empty, Empty, Empty, Red, Empty],
@@ -431,21 +650,224 @@ mod tests {
                 [Empty; 7],
                 [Empty; 7],
             ],
-            Vec::new(),
+            4,
         );
         assert_eq!(board.check_winner(), Red);
     }
 
     #[test]
     fn check_player_stroke() {
+        // pairs of tokens alternate in each column so nobody ever wins
         let mut board = Board::new();
         for col in 0..7 {
             for row in (0..6).rev() {
-                assert_eq!(board.player_stroke(Red, col as i8), Some(true));
-                assert!(board.check_cell(row, col, &Red));
+                let token = if ((5 - row) / 2 + col) % 2 == 0 {
+                    Red
+                } else {
+                    Yellow
+                };
+                assert_eq!(board.player_stroke(token, col as i8), Ok(()));
+                assert!(board.check_cell(row, col, &token));
+                if col == 0 {
+                    assert_eq!(board.player_stroke(Empty, 0), Err(MoveError::WrongTurn));
+                }
+            }
+            if col < 6 {
+                assert_eq!(
+                    board.player_stroke(Red, col as i8),
+                    Err(MoveError::ColumnFull)
+                );
             }
         }
-        assert_eq!(board.player_stroke(Red, 0), Some(false));
-        assert_eq!(board.player_stroke(Red, 5), Some(false));
+        assert_eq!(board.check_winner(), Empty);
+        assert_eq!(board.player_stroke(Red, 0), Err(MoveError::GameOver));
+        assert_eq!(board.random_column(&mut rand::thread_rng()), None);
+        assert_eq!(board.random_stroke(Red, &mut rand::thread_rng()), Err(MoveError::GameOver));
+
+        let mut board = Board::new();
+        assert_eq!(board.player_stroke(Red, 7), Err(MoveError::OutOfRange));
+        assert_eq!(board.player_stroke(Red, -1), Err(MoveError::OutOfRange));
+        for _ in 0..4 {
+            assert_eq!(board.player_stroke(Yellow, 2), Ok(()));
+        }
+        assert_eq!(board.player_stroke(Red, 3), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn same_seed_same_random_strokes() {
+        let strokes = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut board = Board::new();
+            let mut token = Red;
+            while !board.is_over() {
+                assert_eq!(board.random_stroke(token, &mut rng), Ok(()));
+                token = token.opponent();
+            }
+            board.history().to_vec()
+        };
+        assert_eq!(strokes(11), strokes(11));
+        assert_ne!(strokes(11), strokes(12));
+    }
+
+    #[test]
+    fn check_no_winner_across_columns() {
+        // The top three cells of a column followed by the bottom cell of the
+        // next one are contiguous bits but not a line on the board.
+        let board = Board::from_grid(
+            [
+                [Red, Empty, Empty, Empty, Empty, Empty, Empty],
+                [Red, Empty, Empty, Empty, Empty, Empty, Empty],
+                [Red, Empty, Empty, Empty, Empty, Empty, Empty],
+                [Yellow, Empty, Empty, Empty, Empty, Empty, Empty],
+                [Yellow, Empty, Empty, Empty, Empty, Empty, Empty],
+                [Yellow, Red, Empty, Empty, Empty, Empty, Empty],
+            ],
+            4,
+        );
+        assert_eq!(board.check_winner(), Empty);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut board = Board::new();
+        board.player_stroke(Red, 3).unwrap();
+        board.player_stroke(Yellow, 3).unwrap();
+        board.player_stroke(Red, 4).unwrap();
+        assert_eq!(board.history(), &[3, 3, 4]);
+
+        assert_eq!(board.undo(), Some((4, Red)));
+        assert_eq!(board.undo(), Some((3, Yellow)));
+        assert_eq!(board.history(), &[3]);
+        assert!(board.check_cell(5, 3, &Red));
+        assert!(board.check_cell(4, 3, &Empty));
+        assert!(board.check_cell(5, 4, &Empty));
+
+        assert_eq!(board.redo(), Some((3, Yellow)));
+        assert!(board.check_cell(4, 3, &Yellow));
+        assert_eq!(board.history(), &[3, 3]);
+
+        // a new move forgets the moves that are still undone
+        board.player_stroke(Red, 0).unwrap();
+        assert_eq!(board.redo(), None);
+        assert_eq!(board.undo(), Some((0, Red)));
+        assert_eq!(board.undo(), Some((3, Yellow)));
+        assert_eq!(board.undo(), Some((3, Red)));
+        assert_eq!(board.undo(), None);
+    }
+
+    #[test]
+    fn check_connect_five_on_a_larger_board() {
+        let mut board = Board::with_size(9, 7, 5).unwrap();
+        for col in 2..6 {
+            board.player_stroke(Yellow, col).unwrap();
+        }
+        assert_eq!(board.check_winner(), Empty);
+        board.player_stroke(Yellow, 8).unwrap();
+        assert_eq!(board.check_winner(), Empty);
+        board.player_stroke(Yellow, 6).unwrap();
+        assert_eq!(board.check_winner(), Yellow);
+        assert!(board.check_cell(6, 6, &Yellow));
+    }
+
+    #[test]
+    fn check_diagonal_on_a_custom_board() {
+        let board = Board::from_grid(
+            [
+                [Empty, Empty, Empty, Empty, Empty],
+                [Empty, Empty, Red, Empty, Empty],
+                [Empty, Red, Yellow, Empty, Empty],
+                [Red, Yellow, Yellow, Empty, Empty],
+            ],
+            3,
+        );
+        assert_eq!(board.check_winner(), Red);
+    }
+
+    #[test]
+    fn invalid_sizes() {
+        assert_eq!(Board::with_size(0, 6, 4).unwrap_err(), SizeError::Empty);
+        assert_eq!(Board::with_size(16, 8, 4).unwrap_err(), SizeError::TooLarge);
+        assert_eq!(
+            Board::with_size(7, 6, 8).unwrap_err(),
+            SizeError::InvalidConnect
+        );
+        assert_eq!(
+            Board::with_size(7, 6, 1).unwrap_err(),
+            SizeError::InvalidConnect
+        );
+        assert!(Board::with_size(16, 7, 4).is_ok());
+    }
+
+    #[test]
+    fn columns_start_from_the_center() {
+        assert_eq!(Board::new().center_order(), [3, 2, 4, 1, 5, 0, 6]);
+        let board = Board::with_size(8, 7, 4).unwrap();
+        assert_eq!(board.center_order(), [4, 3, 5, 2, 6, 1, 7, 0]);
+    }
+
+    #[test]
+    fn check_winning_line() {
+        assert_eq!(Board::new().winning_line(), None);
+
+        let board = Board::from_grid(
+            [
+                [Empty; 7],
+                [Empty; 7],
+                [Empty; 7],
+                [Empty, Empty, Red, Red, Red, Red, Empty],
+                [Empty; 7],
+                [Empty; 7],
+            ],
+            4,
+        );
+        assert_eq!(
+            board.winning_line(),
+            Some(WinningLine {
+                token: Red,
+                direction: Direction::Horizontal,
+                cells: vec![(3, 2), (3, 3), (3, 4), (3, 5)],
+            })
+        );
+
+        let mut board = Board::new();
+        for _ in 0..4 {
+            board.player_stroke(Yellow, 6).unwrap();
+        }
+        let line = board.winning_line().unwrap();
+        assert_eq!(line.direction, Direction::Vertical);
+        assert_eq!(line.cells, vec![(5, 6), (4, 6), (3, 6), (2, 6)]);
+
+        let board = Board::from_grid(
+            [
+                [Empty, Empty, Empty, Yellow, Empty, Empty, Empty],
+                [Empty, Empty, Yellow, Empty, Empty, Empty, Empty],
+                [Empty, Yellow, Empty, Empty, Empty, Empty, Empty],
+                [Yellow, Empty, Empty, Empty, Empty, Empty, Empty],
+                [Empty; 7],
+                [Empty; 7],
+            ],
+            4,
+        );
+        let line = board.winning_line().unwrap();
+        assert_eq!(
+            (line.token, line.direction),
+            (Yellow, Direction::DiagonalUp)
+        );
+        assert_eq!(line.cells, vec![(3, 0), (2, 1), (1, 2), (0, 3)]);
+
+        let board = Board::from_grid(
+            [
+                [Empty, Empty, Empty, Red, Empty, Empty, Empty],
+                [Empty, Empty, Empty, Empty, Red, Empty, Empty],
+                [Empty, Empty, Empty, Empty, Empty, Red, Empty],
+                [Empty, Empty, Empty, Empty, Empty, Empty, Red],
+                [Empty; 7],
+                [Empty; 7],
+            ],
+            4,
+        );
+        let line = board.winning_line().unwrap();
+        assert_eq!(line.direction, Direction::DiagonalDown);
+        assert_eq!(line.cells, vec![(0, 3), (1, 4), (2, 5), (3, 6)]);
     }
 }
@@ -1,123 +1,378 @@
 use std::io;
 use std::io::Write;
+use std::time::Duration;
 
 use crossterm::{
 	cursor,
-	style::{Color, ResetColor, SetForegroundColor},
+	event::Event,
 	terminal::{Clear, ClearType},
 };
 
-use super::board::{Board, Token};
-
-fn winner_message(board: &Board) {
-	match board.check_winner() {
-		Token::Yellow =>
-			println!(
-				"Victory for the player with the {}yellow tokens !{}",
-				SetForegroundColor(Color::Rgb {
-					r: 255,
-					g: 255,
-					b: 50,
-				}),
-				ResetColor
-			),
-		Token::Red =>
-			println!(
-				"Victory for the player with the {}red tokens !{}",
-				SetForegroundColor(Color::Rgb { r: 255, g: 0, b: 0 }),
-				ResetColor
-			),
-		Token::Empty => println!("The game ended in a draw."),
-	};
-}
+use super::board::{Board, MoveError, Token};
+use super::hint::hint;
+use super::player::{play, Action, Agent, Message, Player};
+use super::random::derive_seeds;
+use super::solver::Solver;
+use super::state::Game;
+use super::render::{Renderer, MARGIN};
+use super::tui::{self, Command, Interface};
 
-fn ask_column(current_player: &Token) -> Result<i8, std::num::ParseIntError> {
-	match current_player {
-		Token::Red => print!(
-			"The player with the {}red token{} must choose a column number : ",
-			SetForegroundColor(Color::Rgb { r: 255, g: 0, b: 0 }),
-			ResetColor
+fn winner_message(renderer: &dyn Renderer, board: &Board) -> String {
+	match board.winning_line() {
+		Some(line) if line.token == Token::Yellow => format!(
+			"Victory for the player with the {} with a {} line !",
+			renderer.name(Token::Yellow, "yellow tokens"),
+			line.direction
 		),
-		Token::Yellow => print!(
-			"The player with the {}yellow token{} must choose a column number : ",
-			SetForegroundColor(Color::Rgb { r: 255, g: 255, b: 50 }),
-			ResetColor
+		Some(line) => format!(
+			"Victory for the player with the {} with a {} line !",
+			renderer.name(Token::Red, "red tokens"),
+			line.direction
 		),
-		Token::Empty => {}
-	};
+		None => "The game ended in a draw.".to_string(),
+	}
+}
+
+// Both notations of the position, they can be given to the solve subcommand
+fn position_message(game: &Game) -> String {
+	format!(
+		"Moves played : {} ({})",
+		game.board().to_notation(),
+		game.board().to_grid_notation(game.turn())
+	)
+}
+
+// Prints the board after a blank line
+fn display(renderer: &dyn Renderer, board: &Board) {
+	println!();
+	for line in renderer.lines(board) {
+		println!("{}", line);
+	}
+}
+
+// Starts again from the top of the terminal, the plain interface only skips a line
+fn clear_screen(interface: &Interface) {
+	if interface.plain {
+		println!();
+	} else {
+		print!("{}{}", Clear(ClearType::FromCursorUp), cursor::MoveTo(0, 0));
+	}
+}
+
+// What a player can type at the prompt
+enum Input {
+	Column(i8),
+	Undo,
+	Redo,
+	Save(String),
+	Hint,
+}
+
+fn show_message(renderer: &dyn Renderer, message: &Message) -> String {
+	match message {
+		Message::Move(err) => renderer.error(&format!("Invalid move : {}.", err)),
+		Message::Saved(file) => format!("Game saved in {}.", file),
+		Message::Save(err) => renderer.error(&format!("Cannot save the game : {}.", err)),
+	}
+}
+
+fn player_name(renderer: &dyn Renderer, token: Token) -> String {
+	match token {
+		Token::Red => renderer.name(token, "red token"),
+		_ => renderer.name(Token::Yellow, "yellow token"),
+	}
+}
+
+fn ask_column(renderer: &dyn Renderer, current_player: &Token) -> Result<Input, MoveError> {
+	if *current_player != Token::Empty {
+		print!(
+			"The player with the {} must choose a column number (u to undo, r to redo, save <file> to save, ? for a hint) : ",
+			player_name(renderer, *current_player)
+		);
+	}
 	std::io::stdout().flush().unwrap();
 
 	let mut col = String::new();
-	io::stdin()
+	let read = io::stdin()
 		.read_line(&mut col)
 		.expect("Error reading user input.");
+	// nothing more will ever be read once the input is closed
+	if read == 0 {
+		println!();
+		std::process::exit(0);
+	}
 
-	col.trim().parse::<i8>()
+	match col.trim() {
+		"u" => Ok(Input::Undo),
+		"r" => Ok(Input::Redo),
+		"hint" | "?" => Ok(Input::Hint),
+		input if input.starts_with("save ") => Ok(Input::Save(input["save ".len()..].trim().to_string())),
+		col => Ok(Input::Column(col.parse::<i8>()?)),
+	}
 }
 
-pub fn against_another_player() {
-	let mut board = Board::new();
-	let mut current_player = Token::Red;
+// The row of the screen where the token hovers, the board is right below
+const HOVER_ROW: u16 = 2;
 
-	while !board.is_full() && !(board.check_winner() != Token::Empty) {
-		println!(
-			"{}{}Current game.",
-			Clear(ClearType::FromCursorUp),
-			cursor::MoveTo(0, 0)
-		);
-		board.display();
+// The lines of the full screen interface: the title, the token hovering over
+// its column if any, the board and the position
+fn screen(renderer: &dyn Renderer, title: &str, game: &Game, hover: Option<(Token, usize)>) -> Vec<String> {
+	let mut lines = vec![title.to_string(), String::new()];
+	lines.push(match hover {
+		Some((token, column)) => tui::hover_line(renderer, token, column),
+		None => String::new(),
+	});
+	lines.extend(renderer.lines(game.board()));
+	lines.push(String::new());
+	lines.push(position_message(game));
+	lines
+}
 
-		let col: i8 = match ask_column(&current_player) {
-			Ok(num) => num,
-			Err(_) => continue,
-		};
+// A player typing the columns at the prompt, who can also undo, redo, save the
+// game and ask for a hint. On the full screen interface the column is chosen
+// with the arrows or the mouse and the token starts above the column played
+// last time, or above the column of the hint.
+#[derive(Default)]
+pub struct Human {
+	column: Option<usize>,
+	interface: Interface,
+	// The number of hints given to the player
+	hints: u32,
+	// Solves the end of the game for the hints
+	solver: Solver,
+}
 
-		// try to place the token in the column selected by the user and deal with the potential problem
-		match board.player_stroke(current_player, col - 1) {
-			None => continue,
-			Some(t) => if !t { continue; }
+impl Human {
+	pub fn new(interface: Interface) -> Human {
+		Human {
+			column: None,
+			interface,
+			hints: 0,
+			solver: Solver::new(),
 		}
+	}
 
-		current_player = if current_player == Token::Red { Token::Yellow } else { Token::Red };
+	// The column recommended to the player and the line telling it, no column
+	// once the player has used all of their hints
+	fn hint(&mut self, renderer: &dyn Renderer, game: &Game) -> (Option<usize>, String) {
+		if let Some(limit) = self.interface.hints.filter(|&limit| self.hints >= limit) {
+			return (None, renderer.error(&format!("No hint left, each player gets {} of them.", limit)));
+		}
+		let hint = hint(game.board(), game.turn(), &mut self.solver).expect("no column left to play");
+		self.hints += 1;
+		let mut line = format!("Hint : play in the column {}, {}.", hint.column + 1, hint.evaluation);
+		if let Some(limit) = self.interface.hints {
+			line += &format!(" Hints left : {}.", limit - self.hints);
+		}
+		(Some(hint.column), line)
 	}
 
-	println!(
-		"{}{}Party to finish.",
-		Clear(ClearType::FromCursorUp),
-		cursor::MoveTo(0, 0)
-	);
-	board.display();
-	winner_message(&board);
+	fn read_action(&mut self, game: &Game, message: Option<&Message>) -> Action {
+		let renderer = self.interface.renderer();
+		let mut note = message.map(|message| show_message(renderer.as_ref(), message));
+		loop {
+			clear_screen(&self.interface);
+			println!("Current game.");
+			display(renderer.as_ref(), game.board());
+			println!("{}", position_message(game));
+			if let Some(note) = note.take() {
+				println!("{}", note);
+			}
+
+			match ask_column(renderer.as_ref(), &game.turn()) {
+				Ok(Input::Column(num)) if num >= 1 => return Action::Play(num as usize - 1),
+				Ok(Input::Column(_)) => note = Some(show_message(renderer.as_ref(), &Message::Move(MoveError::OutOfRange))),
+				Ok(Input::Undo) => return Action::Undo,
+				Ok(Input::Redo) => return Action::Redo,
+				Ok(Input::Save(file)) => return Action::Save(file),
+				Ok(Input::Hint) => note = Some(self.hint(renderer.as_ref(), game).1),
+				Err(err) => note = Some(show_message(renderer.as_ref(), &Message::Move(err))),
+			}
+		}
+	}
+
+	fn press_action(&mut self, game: &Game, message: Option<&Message>) -> Action {
+		let cols = game.board().cols();
+		let mut column = self.column.unwrap_or(cols / 2).min(cols - 1);
+		let renderer = self.interface.renderer();
+		let renderer = renderer.as_ref();
+		let mut note = message.map(|message| show_message(renderer, message));
+		// the board takes the lines of the screen from the hovering token on
+		let bottom = HOVER_ROW + 1 + renderer.lines(game.board()).len() as u16;
+		tui::discard_events();
+		loop {
+			let mut lines = screen(renderer, "Current game.", game, Some((game.turn(), column)));
+			lines.push(format!("The player with the {} must choose a column.", player_name(renderer, game.turn())));
+			lines.push(
+				"<- -> or the mouse to move, Enter, Space or a click to drop, 1-9 for a column, u to undo, r to redo, s to save, h or ? for a hint, q to quit"
+					.to_string(),
+			);
+			if let Some(note) = &note {
+				lines.push(String::new());
+				lines.push(note.clone());
+			}
+			tui::draw(&lines);
+
+			let command = match tui::read_event() {
+				Event::Key(key) => tui::command(key, column, cols),
+				Event::Mouse(mouse) => tui::mouse_command(mouse, HOVER_ROW, bottom, cols),
+				_ => Command::Ignore,
+			};
+			match command {
+				Command::Select(col) => column = col,
+				Command::Drop(col) => {
+					self.column = Some(col);
+					return Action::Play(col);
+				}
+				Command::Undo => return Action::Undo,
+				Command::Redo => return Action::Redo,
+				Command::Save => match tui::read_line(&lines, "Save the game in : ") {
+					Some(file) if !file.trim().is_empty() => return Action::Save(file.trim().to_string()),
+					_ => note = None,
+				},
+				Command::Hint => {
+					lines.push(String::new());
+					lines.push("Looking for a hint...".to_string());
+					tui::draw(&lines);
+					let (col, line) = self.hint(renderer, game);
+					column = col.unwrap_or(column);
+					note = Some(line);
+				}
+				Command::Quit => tui::quit(),
+				Command::Invalid(err) => note = Some(show_message(renderer, &Message::Move(err))),
+				Command::Ignore => {}
+			}
+		}
+	}
 }
 
-pub fn against_computer() {
-	let mut board = Board::new();
+impl Player for Human {
+	fn choose_move(&mut self, board: &Board, token: Token) -> usize {
+		let game = Game::with_first_player(board.clone(), token);
+		loop {
+			if let Action::Play(col) = self.choose_action(&game, None) {
+				return col;
+			}
+		}
+	}
 
-	while !board.is_full() && !(board.check_winner() != Token::Empty) {
-		println!(
-			"{}{}Current game.",
-			Clear(ClearType::FromCursorUp),
-			cursor::MoveTo(0, 0)
-		);
-		board.display();
+	fn choose_action(&mut self, game: &Game, message: Option<&Message>) -> Action {
+		if tui::is_active() {
+			self.press_action(game, message)
+		} else {
+			self.read_action(game, message)
+		}
+	}
 
-		let col: i8 = match ask_column(&Token::Red) {
-			Ok(num) => num,
-			Err(_) => continue,
-		};
+	fn agent(&self) -> Option<Agent> {
+		Some(Agent::Human)
+	}
+}
+
+// The row of the screen of the cell `x` (from the top) of the board, below
+// the hovering token and the column numbers
+fn cell_row(x: usize) -> u16 {
+	HOVER_ROW + 4 + 2 * x as u16
+}
+
+// Shows the token of `token` falling from above `col` down to the free cell of the column
+fn fall(renderer: &dyn Renderer, board: &Board, token: Token, col: usize, delay: Duration) {
+	let Some(landing) = (0..board.rows()).rev().find(|&x| board.cell(x, col) == Some(Token::Empty)) else {
+		return;
+	};
+	let column = (MARGIN + 1 + 4 * col) as u16;
+	let mut row = HOVER_ROW;
+	tui::paint(row, column, &renderer.cell(token));
+	std::thread::sleep(delay);
+	for x in 0..=landing {
+		tui::paint(row, column, &if row == HOVER_ROW { "   ".to_string() } else { renderer.cell(Token::Empty) });
+		row = cell_row(x);
+		tui::paint(row, column, &renderer.cell(token));
+		std::thread::sleep(delay);
+	}
+}
 
-		match board.player_stroke(Token::Red, col - 1) {
-			None => continue,
-			Some(t) => if !t { continue; }
+// A player on the full screen interface, the computer is shown thinking and the
+// tokens played fall down their column
+struct Shown {
+	player: Box<dyn Player>,
+	interface: Interface,
+}
+
+impl Player for Shown {
+	fn choose_move(&mut self, board: &Board, token: Token) -> usize {
+		self.player.choose_move(board, token)
+	}
+
+	fn choose_action(&mut self, game: &Game, message: Option<&Message>) -> Action {
+		let renderer = self.interface.renderer();
+		let renderer = renderer.as_ref();
+		if self.player.agent() != Some(Agent::Human) {
+			let mut lines = screen(renderer, "Current game.", game, None);
+			lines.push(format!("The computer with the {} is thinking...", player_name(renderer, game.turn())));
+			tui::draw(&lines);
+		}
+		let action = self.player.choose_action(game, message);
+		if let (Action::Play(col), Some(delay)) = (&action, self.interface.animation) {
+			if game.board().can_play(*col) {
+				let mut lines = screen(renderer, "Current game.", game, None);
+				lines.push(format!(
+					"The player with the {} plays in the column {}.",
+					player_name(renderer, game.turn()),
+					col + 1
+				));
+				tui::draw(&lines);
+				fall(renderer, game.board(), game.turn(), *col, delay);
+			}
 		}
-		board.random_stroke(Token::Yellow);
+		action
+	}
+
+	fn agent(&self) -> Option<Agent> {
+		self.player.agent()
+	}
+}
+
+// Plays a game in the terminal between the two agents and shows its end, the
+// seeds of the computer players are derived from `seed` when there is one.
+// The game is played on the full screen interface when the terminal allows it,
+// unless the interface is plain.
+pub fn interactive_game(game: Game, red: Agent, yellow: Agent, seed: Option<u64>, interface: Interface) {
+	let seeds = seed.map(|seed| derive_seeds(seed, 2));
+	let seed = |player: usize| seeds.as_ref().map(|seeds| seeds[player]);
+	// the plain interface keeps to lines of text for the screen readers and the logs
+	let screen_shown = if tui::available() && !interface.plain {
+		tui::Screen::enter().ok()
+	} else {
+		None
+	};
+	// the humans are given the interface, the other players do not show anything
+	let player = |agent: Agent, seed| {
+		let player = match agent {
+			Agent::Human => Box::new(Human::new(interface)),
+			_ => agent.player(seed),
+		};
+		match screen_shown {
+			Some(_) => Box::new(Shown { player, interface }),
+			None => player,
+		}
+	};
+	let game = play(game, player(red, seed(0)).as_mut(), player(yellow, seed(1)).as_mut());
+	let renderer = interface.renderer();
+
+	if screen_shown.is_some() {
+		let mut lines = screen(renderer.as_ref(), "Party to finish.", &game, None);
+		lines.push(winner_message(renderer.as_ref(), game.board()));
+		lines.push(String::new());
+		lines.push("Press any key to quit.".to_string());
+		tui::draw(&lines);
+		tui::read_key();
 	}
+	drop(screen_shown);
 
-	println!(
-		"{}{}Party to finish.",
-		Clear(ClearType::FromCursorUp),
-		cursor::MoveTo(0, 0)
-	);
-	board.display();
-	winner_message(&board);
+	clear_screen(&interface);
+	println!("Party to finish.");
+	display(renderer.as_ref(), game.board());
+	println!("{}", position_message(&game));
+	println!("{}", winner_message(renderer.as_ref(), game.board()));
 }
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use super::board::{Board, Token};
+use super::minimax::{search, WIN_SCORE};
+use super::solver::{Outcome, Solver};
+
+// The positions with at most this number of free cells are solved, the others
+// are searched a few moves ahead so that the hint comes quickly
+const SOLVED_CELLS: usize = 20;
+
+// The number of moves searched ahead when the position is not solved
+const HINT_DEPTH: u32 = 6;
+
+// How the game goes on after the recommended move, seen from the player about to play
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Evaluation {
+	// The number of tokens played until the end of the game, the recommended one included
+	Win(usize),
+	Loss(usize),
+	Draw,
+	// The heuristic score of the search when it did not see the end of the game
+	Score(i32),
+}
+
+impl fmt::Display for Evaluation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Evaluation::Win(1) => write!(f, "it wins at once"),
+			Evaluation::Win(moves) => write!(f, "it wins in {} moves", moves),
+			Evaluation::Loss(moves) => write!(f, "it still loses in {} moves", moves),
+			Evaluation::Draw => write!(f, "it leads to a draw"),
+			Evaluation::Score(score) => write!(f, "its heuristic score is {}", score),
+		}
+	}
+}
+
+// The column a search engine recommends to the player about to play
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hint {
+	// The column (starting from 0)
+	pub column: usize,
+	pub evaluation: Evaluation,
+}
+
+// The move recommended to `token` on `board`, none when the game is over. The
+// `solver` keeps its transposition table from one hint to the next
+pub fn hint(board: &Board, token: Token, solver: &mut Solver) -> Option<Hint> {
+	if board.is_over() {
+		return None;
+	}
+	if board.cols() * board.rows() - board.moves() <= SOLVED_CELLS {
+		Some(solved_hint(board, token, solver))
+	} else {
+		searched_hint(board, token)
+	}
+}
+
+// The move with the best outcome under perfect play, each move is solved from
+// the position it leads to
+fn solved_hint(board: &Board, token: Token, solver: &mut Solver) -> Hint {
+	let mut board = board.clone();
+	let mut best: Option<(i32, Hint)> = None;
+	for col in board.center_order() {
+		if !board.can_play(col) {
+			continue;
+		}
+		board.player_stroke(token, col as i8).unwrap();
+		let (score, evaluation) = if board.check_winner() == token {
+			(i32::MAX, Evaluation::Win(1))
+		} else {
+			let solution = solver.solve(&board, token.opponent());
+			let evaluation = match solution.outcome {
+				Outcome::Win => Evaluation::Loss(solution.moves + 1),
+				Outcome::Loss => Evaluation::Win(solution.moves + 1),
+				Outcome::Draw => Evaluation::Draw,
+			};
+			(-solution.score, evaluation)
+		};
+		board.undo();
+		if best.is_none_or(|(best, _)| score > best) {
+			best = Some((score, Hint { column: col, evaluation }));
+		}
+	}
+	best.expect("no column left to play").1
+}
+
+// The best move of the minimax search, its score tells when it sees the end of the game
+fn searched_hint(board: &Board, token: Token) -> Option<Hint> {
+	let (column, score) = search(board, token, HINT_DEPTH)?;
+	let moves = |score: i32| (HINT_DEPTH as i32 + 1 - (score - WIN_SCORE)) as usize;
+	let evaluation = match score {
+		s if s > WIN_SCORE => Evaluation::Win(moves(s)),
+		s if s < -WIN_SCORE => Evaluation::Loss(moves(-s)),
+		s => Evaluation::Score(s),
+	};
+	Some(Hint { column, evaluation })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{hint, Board, Evaluation, Hint, Outcome, Solver, Token::*};
+
+	#[test]
+	fn takes_the_win() {
+		let solver = &mut Solver::new();
+		let board: Board = "445566".parse().unwrap();
+		assert_eq!(
+			hint(&board, Red, solver),
+			Some(Hint {
+				column: 2,
+				evaluation: Evaluation::Win(1),
+			})
+		);
+		// red wins on either side of its three tokens, yellow can only block one of them
+		let board: Board = "44556".parse().unwrap();
+		assert_eq!(hint(&board, Yellow, solver).unwrap().evaluation, Evaluation::Loss(2));
+	}
+
+	#[test]
+	fn solves_the_end_of_the_game() {
+		// the recommended move keeps the value of the position under perfect play
+		let board: Board = "44444333322221155667711".parse().unwrap();
+		let solver = &mut Solver::new();
+		let solution = solver.solve(&board, Yellow);
+		let evaluation = hint(&board, Yellow, solver).unwrap().evaluation;
+		match solution.outcome {
+			Outcome::Win => assert_eq!(evaluation, Evaluation::Win(solution.moves)),
+			Outcome::Loss => assert_eq!(evaluation, Evaluation::Loss(solution.moves)),
+			Outcome::Draw => assert_eq!(evaluation, Evaluation::Draw),
+		}
+
+		let board: Board = "4453".parse().unwrap();
+		assert!(matches!(hint(&board, Red, solver).unwrap().evaluation, Evaluation::Score(_)));
+		assert_eq!(Evaluation::Win(3).to_string(), "it wins in 3 moves");
+	}
+}
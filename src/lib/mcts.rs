@@ -0,0 +1,200 @@
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use super::board::{Board, Token};
+use super::random::playout;
+
+// A node of the search tree, reached by playing `col` from its parent
+struct Node {
+	col: usize,
+	// The player who played `col`
+	player: Token,
+	parent: Option<usize>,
+	children: Vec<usize>,
+	// The moves of the position not yet explored
+	untried: Vec<usize>,
+	visits: u32,
+	// Sum of the results of the playouts for `player`, a draw is worth half a win
+	wins: f64,
+}
+
+impl Node {
+	fn new(board: &Board, col: usize, player: Token, parent: Option<usize>) -> Node {
+		let untried = if board.is_over() {
+			Vec::new()
+		} else {
+			(0..board.cols()).filter(|&col| board.can_play(col)).collect()
+		};
+		Node {
+			col,
+			player,
+			parent,
+			children: Vec::new(),
+			untried,
+			visits: 0,
+			wins: 0.0,
+		}
+	}
+
+	// Upper confidence bound of the node seen from its parent
+	fn uct(&self, parent_visits: u32, exploration: f64) -> f64 {
+		let visits = self.visits as f64;
+		self.wins / visits + exploration * ((parent_visits as f64).ln() / visits).sqrt()
+	}
+}
+
+// Monte Carlo tree search with the UCT selection, the positions are evaluated
+// by playing random games until their end
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Mcts {
+	// The number of playouts of a search
+	pub iterations: u32,
+	// Stops the search before the end of the iterations once elapsed
+	pub time: Option<Duration>,
+	// Weight of the exploration of the less visited moves against the best ones
+	pub exploration: f64,
+}
+
+impl Default for Mcts {
+	fn default() -> Mcts {
+		Mcts {
+			iterations: 10_000,
+			time: None,
+			exploration: std::f64::consts::SQRT_2,
+		}
+	}
+}
+
+impl Mcts {
+	// Returns the column (starting from 0) that `token` should play, the most
+	// visited one. The moves explored and the playouts are drawn from `rng`, at
+	// least one playout is run whatever the iterations and the time budget.
+	pub fn best_move<R: Rng + ?Sized>(&self, board: &Board, token: Token, rng: &mut R) -> Option<usize> {
+		let mut tree = vec![Node::new(board, 0, token.opponent(), None)];
+		if tree[0].untried.len() <= 1 {
+			return tree[0].untried.first().copied();
+		}
+
+		let start = Instant::now();
+		for iteration in 0..self.iterations.max(1) {
+			if iteration > 0 && self.time.is_some_and(|time| start.elapsed() >= time) {
+				break;
+			}
+			let mut board = board.clone();
+			let mut node = 0;
+
+			// selection of the most promising node whose moves are all explored
+			while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+				let parent_visits = tree[node].visits;
+				node = *tree[node]
+					.children
+					.iter()
+					.max_by(|&&a, &&b| {
+						let (a, b) = (tree[a].uct(parent_visits, self.exploration), tree[b].uct(parent_visits, self.exploration));
+						a.total_cmp(&b)
+					})
+					.unwrap();
+				board.player_stroke(tree[node].player, tree[node].col as i8).unwrap();
+			}
+
+			// expansion with one of its unexplored moves
+			if !tree[node].untried.is_empty() {
+				let untried = &mut tree[node].untried;
+				let col = untried.swap_remove(rng.gen_range(0..untried.len()));
+				let player = tree[node].player.opponent();
+				board.player_stroke(player, col as i8).unwrap();
+				tree.push(Node::new(&board, col, player, Some(node)));
+				let child = tree.len() - 1;
+				tree[node].children.push(child);
+				node = child;
+			}
+
+			// simulation of the end of the game
+			let winner = if board.is_over() {
+				board.check_winner()
+			} else {
+				playout(&board, tree[node].player.opponent(), rng)
+			};
+
+			// backpropagation of the result up to the root
+			let mut current = Some(node);
+			while let Some(index) = current {
+				let node = &mut tree[index];
+				node.visits += 1;
+				if winner == node.player {
+					node.wins += 1.0;
+				} else if winner == Token::Empty {
+					node.wins += 0.5;
+				}
+				current = node.parent;
+			}
+		}
+
+		tree[0].children.iter().max_by_key(|&&child| tree[child].visits).map(|&child| tree[child].col)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Board, Mcts, Token::*};
+	use rand::{rngs::StdRng, SeedableRng};
+	use std::time::Duration;
+
+	#[test]
+	fn takes_an_immediate_win() {
+		let board: Board = "171716".parse().unwrap();
+		assert_eq!(Mcts::default().best_move(&board, Red, &mut StdRng::seed_from_u64(1)), Some(0));
+	}
+
+	#[test]
+	fn blocks_an_immediate_loss() {
+		let board: Board = "17273".parse().unwrap();
+		assert_eq!(Mcts::default().best_move(&board, Yellow, &mut StdRng::seed_from_u64(1)), Some(3));
+	}
+
+	#[test]
+	fn only_move() {
+		let mut board = Board::with_size(2, 2, 2).unwrap();
+		board.player_stroke(Red, 0).unwrap();
+		board.player_stroke(Yellow, 0).unwrap();
+		let mcts = Mcts {
+			iterations: 0,
+			..Mcts::default()
+		};
+		assert_eq!(mcts.best_move(&board, Red, &mut StdRng::seed_from_u64(1)), Some(1));
+	}
+
+	#[test]
+	fn plays_without_iterations() {
+		let board: Board = "43".parse().unwrap();
+		for mcts in [
+			Mcts {
+				iterations: 0,
+				..Mcts::default()
+			},
+			Mcts {
+				time: Some(Duration::ZERO),
+				..Mcts::default()
+			},
+		] {
+			let col = mcts.best_move(&board, Red, &mut StdRng::seed_from_u64(1));
+			assert!(col.is_some_and(|col| board.can_play(col)));
+		}
+	}
+
+	#[test]
+	fn same_seed_same_move() {
+		let mcts = Mcts {
+			iterations: 200,
+			..Mcts::default()
+		};
+		let board: Board = "43".parse().unwrap();
+		for seed in 0..5 {
+			let moves: Vec<_> = (0..2)
+				.map(|_| mcts.best_move(&board, Red, &mut StdRng::seed_from_u64(seed)))
+				.collect();
+			assert_eq!(moves[0], moves[1]);
+		}
+	}
+}
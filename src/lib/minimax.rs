@@ -0,0 +1,166 @@
+use super::board::{Board, Token};
+
+// Any score above this one means that a player has connected enough tokens,
+// the remaining depth is added to prefer the quickest win.
+pub const WIN_SCORE: i32 = 1_000_000;
+
+// Scores a window of `connect` cells from the point of view of `token`, open
+// threes and open twos (for a connect four) are the lines that can still be completed.
+fn score_window(window: &[Token], token: Token) -> i32 {
+	let own = window.iter().filter(|&&cell| cell == token).count();
+	let other = window.iter().filter(|&&cell| cell == token.opponent()).count();
+	let connect = window.len();
+	match (own, other) {
+		(n, 0) if n + 1 == connect => 5,
+		(n, 0) if n + 2 == connect => 2,
+		(0, n) if n + 1 == connect => -4,
+		(0, n) if n + 2 == connect => -2,
+		_ => 0,
+	}
+}
+
+// Heuristic evaluation of a position without a winner from the point of view of `token`
+pub fn evaluate(board: &Board, token: Token) -> i32 {
+	let (rows, cols, connect) = (board.rows() as isize, board.cols() as isize, board.connect() as isize);
+	let mut score = 0;
+
+	// tokens in the center column take part in the most lines
+	for x in 0..board.rows() {
+		if board.cell(x, board.cols() / 2) == Some(token) {
+			score += 3;
+		}
+	}
+
+	// every window of `connect` cells in a row, a column or a diagonal
+	let mut window = Vec::with_capacity(board.connect());
+	for x in 0..rows {
+		for y in 0..cols {
+			for (dx, dy) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+				let (end_x, end_y) = (x + dx * (connect - 1), y + dy * (connect - 1));
+				if end_x >= rows || end_y < 0 || end_y >= cols {
+					continue;
+				}
+				window.clear();
+				for k in 0..connect {
+					window.push(board.cell((x + dx * k) as usize, (y + dy * k) as usize).unwrap());
+				}
+				score += score_window(&window, token);
+			}
+		}
+	}
+	score
+}
+
+// Negamax search with alpha-beta pruning, the score is given from the point of
+// view of `token` which is the player about to play. Each move is undone once
+// explored so the board is left as it was given.
+fn negamax(board: &mut Board, token: Token, depth: u32, mut alpha: i32, beta: i32) -> i32 {
+	if board.is_full() {
+		return 0;
+	}
+	if depth == 0 {
+		return evaluate(board, token);
+	}
+
+	let mut best = -WIN_SCORE - depth as i32;
+	for col in board.center_order() {
+		if !board.can_play(col) {
+			continue;
+		}
+		board.player_stroke(token, col as i8).unwrap();
+		let score = if board.check_winner() == token {
+			WIN_SCORE + depth as i32
+		} else {
+			-negamax(board, token.opponent(), depth - 1, -beta, -alpha)
+		};
+		board.undo();
+		if score > best {
+			best = score;
+		}
+		if best > alpha {
+			alpha = best;
+		}
+		if alpha >= beta {
+			break;
+		}
+	}
+	best
+}
+
+// Returns the column (starting from 0) that `token` should play, searching `depth` moves ahead
+pub fn best_move(board: &Board, token: Token, depth: u32) -> Option<usize> {
+	search(board, token, depth).map(|(col, _)| col)
+}
+
+// Returns the best column for `token` along with its score, a win found `n`
+// tokens ahead is worth `WIN_SCORE + depth + 1 - n` and a loss the opposite
+pub fn search(board: &Board, token: Token, depth: u32) -> Option<(usize, i32)> {
+	let depth = depth.max(1);
+	let mut board = board.clone();
+	let board = &mut board;
+	let mut best = None;
+	let mut alpha = -WIN_SCORE - depth as i32 - 1;
+	let beta = WIN_SCORE + depth as i32 + 1;
+	for col in board.center_order() {
+		if !board.can_play(col) {
+			continue;
+		}
+		board.player_stroke(token, col as i8).unwrap();
+		let score = if board.check_winner() == token {
+			WIN_SCORE + depth as i32
+		} else {
+			-negamax(board, token.opponent(), depth - 1, -beta, -alpha)
+		};
+		board.undo();
+		if best.is_none() || score > alpha {
+			alpha = score;
+			best = Some((col, score));
+		}
+	}
+	best
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{best_move, evaluate, Board, Token::*};
+
+	#[test]
+	fn open_lines_are_scored() {
+		let board: Board = "172".parse().unwrap();
+		assert!(evaluate(&board, Red) > evaluate(&board, Yellow));
+	}
+
+	#[test]
+	fn blocks_a_connect_five() {
+		let mut board = Board::with_size(9, 7, 5).unwrap();
+		for (token, col) in [(Red, 2), (Yellow, 8), (Red, 3), (Yellow, 8), (Red, 4), (Yellow, 1), (Red, 5)] {
+			board.player_stroke(token, col).unwrap();
+		}
+		assert_eq!(best_move(&board, Yellow, 3), Some(6));
+	}
+
+	#[test]
+	fn takes_an_immediate_win() {
+		let board: Board = "171716".parse().unwrap();
+		assert_eq!(best_move(&board, Red, 4), Some(0));
+	}
+
+	#[test]
+	fn blocks_an_immediate_loss() {
+		let board: Board = "17273".parse().unwrap();
+		assert_eq!(best_move(&board, Yellow, 4), Some(3));
+	}
+
+	#[test]
+	fn no_move_on_a_full_board() {
+		let mut board = Board::new();
+		for col in 0..7 {
+			for row in 0..6 {
+				let token = if (row / 2 + col) % 2 == 0 { Red } else { Yellow };
+				board.player_stroke(token, col as i8).unwrap();
+			}
+		}
+		assert!(board.is_full());
+		assert_eq!(best_move(&board, Red, 4), None);
+	}
+}
@@ -1,6 +1,29 @@
-pub use game::{against_another_player, against_computer};
+pub use board::{Board, Token};
+pub use game::interactive_game;
+pub use mcts::Mcts;
+pub use player::Agent;
 pub use random::game_session;
+pub use save::SavedGame;
+pub use solver::{solve, Outcome};
+pub use state::Game;
+pub use stats::Format;
+pub use theme::Theme;
+pub use tournament::tournament;
+pub use tui::Interface;
 
 mod board;
 mod game;
+mod hint;
+mod mcts;
+mod minimax;
+mod notation;
+mod player;
 mod random;
+mod render;
+mod save;
+mod solver;
+mod state;
+mod stats;
+mod theme;
+mod tournament;
+mod tui;
@@ -0,0 +1,328 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::board::{Board, MoveError, SizeError, Token, CONNECT, MAX_COLS};
+use super::state::Game;
+
+// Two notations are understood:
+//
+// - the columns played since the start of the game, red first, like "4453".
+//   Columns start from 1, the columns after the 9th are written with the
+//   letters a, b, c... up to z for the 35th. The columns of the wider boards
+//   are written as numbers separated by commas, like "38,40,1".
+// - a grid like "7/7/7/7/3r3/3ry2 r" inspired by the FEN notation of chess.
+//   The rows are given from the top, separated by '/', with 'r' and 'y' for
+//   the tokens and numbers for the runs of empty cells. The color of the player
+//   about to play follows, then optionally the number of tokens to connect.
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum NotationError {
+	// The move at the given index is not a column of the board
+	InvalidColumn(usize, String),
+	// The move at the given index is played in a full column
+	ColumnFull(usize),
+	// The move at the given index is played after the end of the game
+	GameOver(usize),
+	// A player already won in the position, there is nothing left to solve
+	Decided,
+	// The character is not a token or a number of empty cells in the given row
+	InvalidCell(usize, char),
+	// The given row does not have as many cells as the first one
+	RowLength(usize),
+	// The given row has more cells than the widest board
+	TooManyCells(usize),
+	// A token of the given column lies above an empty cell
+	FloatingToken(usize),
+	// The player about to play is missing or is not 'r' or 'y'
+	InvalidPlayer,
+	// The number of tokens of each player does not allow this player to play
+	TokenCount,
+	// The number of tokens to connect is not a number
+	InvalidConnect,
+	Size(SizeError),
+}
+
+impl fmt::Display for NotationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			NotationError::InvalidColumn(i, c) => write!(f, "move {} : '{}' is not a column of the board", i + 1, c),
+			NotationError::ColumnFull(i) => write!(f, "move {} : the column is already full", i + 1),
+			NotationError::GameOver(i) => write!(f, "move {} : the game is already over", i + 1),
+			NotationError::Decided => write!(f, "a player already won in this position"),
+			NotationError::InvalidCell(row, c) => write!(f, "row {} : '{}' is not a token or a number of empty cells", row + 1, c),
+			NotationError::RowLength(row) => write!(f, "row {} : the number of cells differs from the first row", row + 1),
+			NotationError::TooManyCells(row) => write!(f, "row {} : a board has at most {} columns", row + 1, MAX_COLS),
+			NotationError::FloatingToken(col) => write!(f, "column {} : a token lies above an empty cell", col + 1),
+			NotationError::InvalidPlayer => write!(f, "the player about to play must be 'r' or 'y'"),
+			NotationError::TokenCount => write!(f, "the number of tokens of each player does not match the player about to play"),
+			NotationError::InvalidConnect => write!(f, "the number of tokens to connect is not a number"),
+			NotationError::Size(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl std::error::Error for NotationError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			NotationError::Size(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<SizeError> for NotationError {
+	fn from(err: SizeError) -> NotationError {
+		NotationError::Size(err)
+	}
+}
+
+// The number of columns that can be written with a single character, 1 to 9 then a to z
+const LETTER_COLS: usize = 35;
+
+// Plays the sequence of columns on the game
+fn play_moves(mut game: Game, moves: &str) -> Result<Game, NotationError> {
+	// each move with the column it gives (starting from 1) if it is a number
+	let moves: Vec<(String, Option<usize>)> = if game.board().cols() <= LETTER_COLS {
+		moves.chars().map(|c| (c.to_string(), c.to_digit(36).map(|col| col as usize))).collect()
+	} else if moves.is_empty() {
+		Vec::new()
+	} else {
+		moves.split(',').map(|col| (col.to_string(), col.trim().parse().ok())).collect()
+	};
+	for (i, (text, col)) in moves.into_iter().enumerate() {
+		let col = match col {
+			Some(col) if col >= 1 && col <= game.board().cols() => col - 1,
+			_ => return Err(NotationError::InvalidColumn(i, text)),
+		};
+		match game.play(game.turn(), col as i8) {
+			Err(MoveError::ColumnFull) => return Err(NotationError::ColumnFull(i)),
+			Err(_) => return Err(NotationError::GameOver(i)),
+			Ok(()) => {}
+		}
+	}
+	Ok(game)
+}
+
+fn parse_row(index: usize, row: &str) -> Result<Vec<Token>, NotationError> {
+	let mut cells = Vec::new();
+	let mut empty: usize = 0;
+	for c in row.chars() {
+		if let Some(digit) = c.to_digit(10) {
+			// the cells are only added once the run is known to fit in a board
+			empty = empty
+				.checked_mul(10)
+				.and_then(|empty| empty.checked_add(digit as usize))
+				.filter(|&empty| cells.len() + empty <= MAX_COLS)
+				.ok_or(NotationError::TooManyCells(index))?;
+			continue;
+		}
+		if cells.len() + empty >= MAX_COLS {
+			return Err(NotationError::TooManyCells(index));
+		}
+		cells.extend(std::iter::repeat_n(Token::Empty, empty));
+		empty = 0;
+		match c {
+			'r' | 'R' => cells.push(Token::Red),
+			'y' | 'Y' => cells.push(Token::Yellow),
+			_ => return Err(NotationError::InvalidCell(index, c)),
+		}
+	}
+	cells.extend(std::iter::repeat_n(Token::Empty, empty));
+	Ok(cells)
+}
+
+fn parse_grid(grid: &str, connect: usize) -> Result<Game, NotationError> {
+	let mut fields = grid.split_whitespace();
+	let rows = fields
+		.next()
+		.unwrap_or("")
+		.split('/')
+		.enumerate()
+		.map(|(i, row)| parse_row(i, row))
+		.collect::<Result<Vec<_>, _>>()?;
+	let player = match fields.next() {
+		Some("r") | Some("R") => Token::Red,
+		Some("y") | Some("Y") => Token::Yellow,
+		_ => return Err(NotationError::InvalidPlayer),
+	};
+	let connect = match fields.next() {
+		Some(connect) => connect.parse().map_err(|_| NotationError::InvalidConnect)?,
+		None => connect,
+	};
+
+	if let Some(row) = rows.iter().position(|row| row.len() != rows[0].len()) {
+		return Err(NotationError::RowLength(row));
+	}
+	for col in 0..rows[0].len() {
+		let column = rows.iter().map(|row| row[col]);
+		if column.skip_while(|&cell| cell == Token::Empty).any(|cell| cell == Token::Empty) {
+			return Err(NotationError::FloatingToken(col));
+		}
+	}
+
+	let count = |token| rows.iter().flatten().filter(|&&cell| cell == token).count();
+	let (red, yellow) = (count(Token::Red), count(Token::Yellow));
+	let valid = match player {
+		Token::Red => red == yellow || red + 1 == yellow,
+		_ => red == yellow || yellow + 1 == red,
+	};
+	if !valid {
+		return Err(NotationError::TokenCount);
+	}
+
+	let board = Board::from_rows(&rows, connect)?;
+	Ok(Game::with_first_player(board, player))
+}
+
+// Reads a position in one of the notations. A sequence of columns is played on
+// `board`, which gives the size of the board, while a grid gives its own size
+// and only takes from `board` the number of tokens to connect.
+pub fn parse_notation(board: Board, notation: &str) -> Result<Game, NotationError> {
+	let notation = notation.trim();
+	if notation.contains('/') || notation.contains(char::is_whitespace) {
+		parse_grid(notation, board.connect())
+	} else {
+		play_moves(Game::new(board), notation)
+	}
+}
+
+impl Board {
+	// The columns played since the start of the game
+	pub fn to_notation(&self) -> String {
+		let history = self.history().iter();
+		if self.cols() <= LETTER_COLS {
+			history.map(|&col| std::char::from_digit(col as u32 + 1, 36).unwrap()).collect()
+		} else {
+			history.map(|&col| (col + 1).to_string()).collect::<Vec<_>>().join(",")
+		}
+	}
+
+	// The grid of the board, followed by the player about to play and the
+	// number of tokens to connect when it is not the usual 4
+	pub fn to_grid_notation(&self, player: Token) -> String {
+		let mut rows = Vec::with_capacity(self.rows());
+		for x in 0..self.rows() {
+			let mut row = String::new();
+			let mut empty = 0;
+			for y in 0..self.cols() {
+				let cell = match self.cell(x, y).unwrap() {
+					Token::Empty => {
+						empty += 1;
+						continue;
+					}
+					Token::Red => 'r',
+					Token::Yellow => 'y',
+				};
+				if empty > 0 {
+					row.push_str(&empty.to_string());
+					empty = 0;
+				}
+				row.push(cell);
+			}
+			if empty > 0 {
+				row.push_str(&empty.to_string());
+			}
+			rows.push(row);
+		}
+
+		let player = if player == Token::Yellow { 'y' } else { 'r' };
+		if self.connect() == CONNECT {
+			format!("{} {}", rows.join("/"), player)
+		} else {
+			format!("{} {} {}", rows.join("/"), player, self.connect())
+		}
+	}
+
+	// Reads a position on the classic board, see `parse_notation`
+	pub fn from_notation(notation: &str) -> Result<Board, NotationError> {
+		parse_notation(Board::new(), notation).map(|game| game.board().clone())
+	}
+}
+
+impl FromStr for Board {
+	type Err = NotationError;
+
+	fn from_str(notation: &str) -> Result<Board, NotationError> {
+		Board::from_notation(notation)
+	}
+}
+
+impl FromStr for Game {
+	type Err = NotationError;
+
+	fn from_str(notation: &str) -> Result<Game, NotationError> {
+		parse_notation(Board::new(), notation)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_notation, Board, Game, NotationError, SizeError, Token::*};
+
+	#[test]
+	fn moves_round_trip() {
+		let board: Board = "4453".parse().unwrap();
+		assert_eq!(board.history(), &[3, 3, 4, 2]);
+		assert_eq!(board.to_notation(), "4453");
+		assert!(board.cell(5, 3) == Some(Red) && board.cell(4, 3) == Some(Yellow));
+
+		let game = parse_notation(Board::with_size(12, 6, 4).unwrap(), "1ac").unwrap();
+		assert_eq!(game.board().history(), &[0, 9, 11]);
+		assert_eq!(game.board().to_notation(), "1ac");
+		assert_eq!(game.turn(), Yellow);
+
+		// the columns of the wide boards are numbers
+		let game = parse_notation(Board::with_size(40, 2, 4).unwrap(), "38,40,1").unwrap();
+		assert_eq!(game.board().history(), &[37, 39, 0]);
+		assert_eq!(game.board().to_notation(), "38,40,1");
+		assert_eq!(
+			parse_notation(Board::with_size(40, 2, 4).unwrap(), "38,41").err(),
+			Some(NotationError::InvalidColumn(1, "41".to_string()))
+		);
+	}
+
+	#[test]
+	fn grid_round_trip() {
+		let board: Board = "4453".parse().unwrap();
+		assert_eq!(board.to_grid_notation(Red), "7/7/7/7/3y3/2yrr2 r");
+
+		let game: Game = "7/7/7/7/3y3/2yrr2 r".parse().unwrap();
+		assert_eq!(game.turn(), Red);
+		assert_eq!(game.board().to_grid_notation(game.turn()), "7/7/7/7/3y3/2yrr2 r");
+		assert_eq!(game.board().cell(5, 2), Some(Yellow));
+		assert_eq!(game.board().history(), &[] as &[usize]);
+
+		let game: Game = "5/5/5/y1rr1 y 3".parse().unwrap();
+		assert_eq!(game.turn(), Yellow);
+		let game: Game = "5/5/5/y1r2 r 3".parse().unwrap();
+		assert_eq!((game.board().cols(), game.board().rows(), game.board().connect()), (5, 4, 3));
+		assert_eq!(game.turn(), Red);
+		assert_eq!(game.board().to_grid_notation(Red), "5/5/5/y1r2 r 3");
+	}
+
+	#[test]
+	fn malformed_moves() {
+		let invalid = |i, c: &str| Some(NotationError::InvalidColumn(i, c.to_string()));
+		assert_eq!(Board::from_notation("12x4").err(), invalid(2, "x"));
+		assert_eq!(Board::from_notation("128").err(), invalid(2, "8"));
+		assert_eq!(Board::from_notation("10").err(), invalid(1, "0"));
+		assert_eq!(Board::from_notation("1111111").err(), Some(NotationError::ColumnFull(6)));
+		assert_eq!(Board::from_notation("12121213").err(), Some(NotationError::GameOver(7)));
+	}
+
+	#[test]
+	fn malformed_grids() {
+		assert_eq!(Board::from_notation("7/7/3x3 r").err(), Some(NotationError::InvalidCell(2, 'x')));
+		assert_eq!(Board::from_notation("7/6/7 r").err(), Some(NotationError::RowLength(1)));
+		assert_eq!(Board::from_notation("99999999999999999999 r").err(), Some(NotationError::TooManyCells(0)));
+		assert_eq!(Board::from_notation("7/65 r").err(), Some(NotationError::TooManyCells(1)));
+		assert_eq!(Board::from_notation("7/64r y").err(), Some(NotationError::TooManyCells(1)));
+		assert_eq!(Board::from_notation("7/r6/7 y").err(), Some(NotationError::FloatingToken(0)));
+		assert_eq!(Board::from_notation("7/7/r6").err(), Some(NotationError::InvalidPlayer));
+		assert_eq!(Board::from_notation("7/7/r6 b").err(), Some(NotationError::InvalidPlayer));
+		assert_eq!(Board::from_notation("7/7/r6 r").err(), Some(NotationError::TokenCount));
+		assert_eq!(Board::from_notation("7/r6/rr5 y").err(), Some(NotationError::TokenCount));
+		assert_eq!(Board::from_notation("7/7/r6 y four").err(), Some(NotationError::InvalidConnect));
+		assert_eq!(Board::from_notation("7/7/r6 y 9").err(), Some(NotationError::Size(SizeError::InvalidConnect)));
+	}
+}
@@ -0,0 +1,374 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::board::{Board, MoveError, Token};
+use super::game::Human;
+use super::mcts::Mcts;
+use super::minimax::best_move;
+use super::save::{SaveError, SavedGame};
+use super::state::Game;
+
+// What a player does when it is their turn
+pub enum Action {
+	// Drops a token in the column (starting from 0)
+	Play(usize),
+	Undo,
+	Redo,
+	// Saves the game in the file
+	Save(String),
+}
+
+// The outcome of the last action, given to the player about to act
+pub enum Message {
+	Move(MoveError),
+	Saved(String),
+	Save(SaveError),
+}
+
+pub trait Player {
+	// Returns the column (starting from 0) where `token` plays on `board`
+	fn choose_move(&mut self, board: &Board, token: Token) -> usize;
+
+	// Plays a move unless overridden, `message` tells the outcome of the previous action
+	fn choose_action(&mut self, game: &Game, _message: Option<&Message>) -> Action {
+		Action::Play(self.choose_move(game.board(), game.turn()))
+	}
+
+	// How the player is written in the save files, the games of the players
+	// without one cannot be saved
+	fn agent(&self) -> Option<Agent> {
+		None
+	}
+}
+
+// Plays random moves
+pub struct Random {
+	rng: StdRng,
+}
+
+impl Random {
+	pub fn new() -> Random {
+		Random {
+			rng: StdRng::from_entropy(),
+		}
+	}
+
+	// Always plays the same moves in the same positions for the same seed
+	pub fn with_seed(seed: u64) -> Random {
+		Random {
+			rng: StdRng::seed_from_u64(seed),
+		}
+	}
+}
+
+impl Default for Random {
+	fn default() -> Random {
+		Random::new()
+	}
+}
+
+impl Player for Random {
+	fn choose_move(&mut self, board: &Board, _token: Token) -> usize {
+		board.random_column(&mut self.rng).expect("no column left to play")
+	}
+
+	fn agent(&self) -> Option<Agent> {
+		Some(Agent::Random)
+	}
+}
+
+// Searches `depth` moves ahead with the negamax algorithm
+pub struct Minimax {
+	pub depth: u32,
+}
+
+impl Player for Minimax {
+	fn choose_move(&mut self, board: &Board, token: Token) -> usize {
+		best_move(board, token, self.depth).expect("no column left to play")
+	}
+
+	fn agent(&self) -> Option<Agent> {
+		Some(Agent::Minimax { depth: self.depth })
+	}
+}
+
+// Searches with the Monte Carlo tree search
+pub struct MonteCarlo {
+	pub mcts: Mcts,
+	rng: StdRng,
+}
+
+impl MonteCarlo {
+	pub fn new(mcts: Mcts) -> MonteCarlo {
+		MonteCarlo {
+			mcts,
+			rng: StdRng::from_entropy(),
+		}
+	}
+
+	pub fn with_seed(mcts: Mcts, seed: u64) -> MonteCarlo {
+		MonteCarlo {
+			mcts,
+			rng: StdRng::seed_from_u64(seed),
+		}
+	}
+}
+
+impl Player for MonteCarlo {
+	fn choose_move(&mut self, board: &Board, token: Token) -> usize {
+		self.mcts.best_move(board, token, &mut self.rng).expect("no column left to play")
+	}
+
+	fn agent(&self) -> Option<Agent> {
+		Some(Agent::Mcts(self.mcts))
+	}
+}
+
+// The description of a player, written like `human`, `random`, `minimax:5` or
+// `mcts:10000:1.4:500` with the number of iterations, the exploration constant
+// and the time budget in milliseconds. The settings left out take their default value.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Agent {
+	Human,
+	Random,
+	Minimax { depth: u32 },
+	Mcts(Mcts),
+}
+
+impl Agent {
+	// The player described, the random players and the Monte Carlo ones draw
+	// their moves from `seed` when there is one
+	pub fn player(&self, seed: Option<u64>) -> Box<dyn Player> {
+		match (*self, seed) {
+			(Agent::Human, _) => Box::new(Human::default()),
+			(Agent::Random, Some(seed)) => Box::new(Random::with_seed(seed)),
+			(Agent::Random, None) => Box::new(Random::new()),
+			(Agent::Minimax { depth }, _) => Box::new(Minimax { depth }),
+			(Agent::Mcts(mcts), Some(seed)) => Box::new(MonteCarlo::with_seed(mcts, seed)),
+			(Agent::Mcts(mcts), None) => Box::new(MonteCarlo::new(mcts)),
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AgentError {
+	Unknown(String),
+	// The setting of the agent cannot be read
+	InvalidSetting(String),
+}
+
+impl fmt::Display for AgentError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			AgentError::Unknown(name) => write!(f, "unknown player '{}', expected human, random, minimax or mcts", name),
+			AgentError::InvalidSetting(setting) => write!(f, "invalid setting '{}'", setting),
+		}
+	}
+}
+
+impl std::error::Error for AgentError {}
+
+impl fmt::Display for Agent {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Agent::Human => write!(f, "human"),
+			Agent::Random => write!(f, "random"),
+			Agent::Minimax { depth } => write!(f, "minimax:{}", depth),
+			// the exploration is left out when it is the default one and there is no time budget
+			Agent::Mcts(mcts) => {
+				write!(f, "mcts:{}", mcts.iterations)?;
+				if mcts.time.is_some() || mcts.exploration != Mcts::default().exploration {
+					write!(f, ":{}", mcts.exploration)?;
+				}
+				match mcts.time {
+					Some(time) => write!(f, ":{}", time.as_millis()),
+					None => Ok(()),
+				}
+			}
+		}
+	}
+}
+
+fn parse_setting<T: FromStr>(setting: Option<&str>, default: T) -> Result<T, AgentError> {
+	match setting {
+		Some(setting) => setting.parse().map_err(|_| AgentError::InvalidSetting(setting.to_string())),
+		None => Ok(default),
+	}
+}
+
+impl FromStr for Agent {
+	type Err = AgentError;
+
+	fn from_str(agent: &str) -> Result<Agent, AgentError> {
+		let mut settings = agent.trim().split(':');
+		let agent = match settings.next().unwrap_or("") {
+			"human" => Agent::Human,
+			"random" => Agent::Random,
+			"minimax" => Agent::Minimax {
+				depth: parse_setting(settings.next(), 5)?,
+			},
+			"mcts" => {
+				let default = Mcts::default();
+				// a search needs at least one random game
+				let iterations = settings.next();
+				if iterations.is_some_and(|iterations| iterations.parse() == Ok(0)) {
+					return Err(AgentError::InvalidSetting(iterations.unwrap().to_string()));
+				}
+				Agent::Mcts(Mcts {
+					iterations: parse_setting(iterations, default.iterations)?,
+					exploration: parse_setting(settings.next(), default.exploration)?,
+					time: match settings.next() {
+						Some(time) => Some(std::time::Duration::from_millis(parse_setting(Some(time), 0)?)),
+						None => None,
+					},
+				})
+			}
+			name => return Err(AgentError::Unknown(name.to_string())),
+		};
+		match settings.next() {
+			Some(setting) => Err(AgentError::InvalidSetting(setting.to_string())),
+			None => Ok(agent),
+		}
+	}
+}
+
+// Plays the game until its end, each player choosing the moves of its color.
+// An undo or a redo asked by a human goes on until a human is about to play
+// again, so the moves of the computer are taken back along with theirs.
+pub fn play(mut game: Game, red: &mut dyn Player, yellow: &mut dyn Player) -> Game {
+	let (red_agent, yellow_agent) = (red.agent(), yellow.agent());
+	let humans = [red_agent == Some(Agent::Human), yellow_agent == Some(Agent::Human)];
+	let human_turn = |game: &Game| humans[(game.turn() == Token::Yellow) as usize];
+	let mut message = None;
+
+	while !game.is_over() {
+		let player: &mut dyn Player = if game.turn() == Token::Red { red } else { yellow };
+		match player.choose_action(&game, message.take().as_ref()) {
+			Action::Play(col) => {
+				// a column too large for the board must not wrap around to a valid one
+				let played = i8::try_from(col)
+					.map_err(|_| MoveError::OutOfRange)
+					.and_then(|col| game.play(game.turn(), col));
+				if let Err(err) = played {
+					message = Some(Message::Move(err));
+				}
+			}
+			Action::Undo => {
+				if game.undo().is_some() {
+					while !human_turn(&game) && game.undo().is_some() {}
+				}
+			}
+			Action::Redo => {
+				if game.redo().is_some() {
+					while !human_turn(&game) && game.redo().is_some() {}
+				}
+			}
+			Action::Save(file) => {
+				let saved = match (red_agent, yellow_agent) {
+					(Some(red), Some(yellow)) => SavedGame::new(red, yellow, game.clone()).save(&file),
+					_ => Err(SaveError::Players),
+				};
+				message = Some(match saved {
+					Ok(()) => Message::Saved(file),
+					Err(err) => Message::Save(err),
+				});
+			}
+		}
+	}
+	game
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{play, Action, Agent, Board, Game, Mcts, Message, Minimax, Player, Random, Token, Token::*};
+	use std::time::Duration;
+
+	#[test]
+	fn agents_round_trip() {
+		for agent in ["human", "random", "minimax:3", "mcts:500", "mcts:500:0.5", "mcts:500:0.5:250"] {
+			assert_eq!(agent.parse::<Agent>().unwrap().to_string(), agent);
+		}
+		assert_eq!("minimax".parse(), Ok(Agent::Minimax { depth: 5 }));
+		assert_eq!("mcts".parse(), Ok(Agent::Mcts(Mcts::default())));
+		assert_eq!(
+			"mcts:500:1:20".parse::<Agent>().unwrap(),
+			Agent::Mcts(Mcts {
+				iterations: 500,
+				exploration: 1.0,
+				time: Some(Duration::from_millis(20)),
+			})
+		);
+		assert!("robot".parse::<Agent>().is_err());
+		assert!("minimax:x".parse::<Agent>().is_err());
+		assert!("mcts:0".parse::<Agent>().is_err());
+		assert!("random:3".parse::<Agent>().is_err());
+	}
+
+	#[test]
+	fn random_players_finish_the_game() {
+		let game = play(Game::new(Board::new()), &mut Random::new(), &mut Random::new());
+		assert!(game.is_over());
+
+		let history = |seed| {
+			let game = play(Game::new(Board::new()), &mut Random::with_seed(seed), &mut Random::with_seed(seed + 1));
+			game.board().history().to_vec()
+		};
+		assert_eq!(history(7), history(7));
+	}
+
+	// Always plays the first free column from the right
+	struct Right;
+
+	impl Player for Right {
+		fn choose_move(&mut self, board: &Board, _token: Token) -> usize {
+			(0..board.cols()).rev().find(|&col| board.can_play(col)).unwrap()
+		}
+	}
+
+	#[test]
+	fn minimax_beats_a_naive_player() {
+		let game = play(Game::new(Board::new()), &mut Minimax { depth: 4 }, &mut Right);
+		assert_eq!(game.board().check_winner(), Red);
+	}
+
+	// Plays the actions in order like a human would
+	struct Script(Vec<Action>);
+
+	impl Player for Script {
+		fn choose_move(&mut self, _board: &Board, _token: Token) -> usize {
+			unreachable!()
+		}
+
+		fn choose_action(&mut self, _game: &Game, _message: Option<&Message>) -> Action {
+			self.0.remove(0)
+		}
+
+		fn agent(&self) -> Option<Agent> {
+			Some(Agent::Human)
+		}
+	}
+
+	#[test]
+	fn undo_takes_back_the_computer_move() {
+		let mut human = Script(vec![Action::Play(0), Action::Undo, Action::Redo, Action::Undo, Action::Play(1), Action::Play(0)]);
+		let game = play(Game::new(Board::with_size(4, 1, 4).unwrap()), &mut human, &mut Right);
+		assert_eq!(game.board().history(), &[1, 3, 0, 2]);
+
+		// a human against another undoes a single move
+		let mut red = Script(vec![Action::Play(0), Action::Play(2), Action::Play(2)]);
+		let mut yellow = Script(vec![Action::Play(1), Action::Undo, Action::Play(3)]);
+		let game = play(Game::new(Board::with_size(4, 1, 4).unwrap()), &mut red, &mut yellow);
+		assert_eq!(game.board().history(), &[0, 1, 2, 3]);
+	}
+
+	#[test]
+	fn columns_out_of_the_board_are_refused() {
+		// 256 would wrap around to the first column
+		let mut red = Script(vec![Action::Play(256), Action::Play(0), Action::Play(2)]);
+		let mut yellow = Script(vec![Action::Play(1), Action::Play(3)]);
+		let game = play(Game::new(Board::with_size(4, 1, 4).unwrap()), &mut red, &mut yellow);
+		assert_eq!(game.board().history(), &[0, 1, 2, 3]);
+	}
+}
@@ -1,98 +1,249 @@
-use std::sync::mpsc::Sender;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
 use super::board::{Board, Token};
+use super::state::{Game, Status};
+use super::stats::{Progress, Session, Stats};
 
-// Start a round between two fictitious players who play random moves
-pub fn round(rand_first_player: bool) -> Token {
-	let mut board = Board::new();
+// Start a round between two fictitious players who play random moves from the position of `board`,
+// returns the first player and the finished game
+pub fn round<R: Rng + ?Sized>(board: &Board, rand_first_player: bool, rng: &mut R) -> (Token, Game) {
 	// randomly choose the first player or not
-	let mut current_player =
+	let first_player =
 		if rand_first_player {
 			[Token::Red, Token::Yellow]
-				.choose(&mut thread_rng())
+				.choose(rng)
 				.unwrap()
 				.to_owned()
 		} else {
 			Token::Red
 		};
 
-	while !board.is_full() && !(board.check_winner() != Token::Empty) {
-		board.random_stroke(current_player);
-		current_player =
-			if current_player == Token::Red {
-				Token::Yellow
-			} else {
-				Token::Red
-			};
+	(first_player, random_game(board, first_player, rng))
+}
+
+// Plays random moves from the position of `board` until the end of the game and returns the winner.
+// `first_player` only plays first when the board has no history to tell whose turn it is.
+pub fn playout<R: Rng + ?Sized>(board: &Board, first_player: Token, rng: &mut R) -> Token {
+	winner(&random_game(board, first_player, rng))
+}
+
+// Both players draw their moves from `rng`, seeding a generator for each game
+// would cost more than the game itself
+fn random_game<R: Rng + ?Sized>(board: &Board, first_player: Token, rng: &mut R) -> Game {
+	let mut board = board.clone();
+	let mut token = Game::turn_after(&board, first_player);
+	while !board.is_over() {
+		board.random_stroke(token, rng).unwrap();
+		token = token.opponent();
 	}
-	// returns the winner of the game
-	board.check_winner()
+	Game::with_first_player(board, first_player)
 }
 
-// Launches a number of rounds between two fictitious players who play random moves
-fn rounds(number_rounds: u64, tx: Sender<[(Token, u64); 3]>, rand_first_player: bool) {
-	let mut res: [(Token, u64); 3] = [(Token::Red, 0), (Token::Yellow, 0), (Token::Empty, 0)];
-	for _i in 0..number_rounds {
-		match round(rand_first_player) {
-			Token::Red => res[0].1 += 1,
-			Token::Yellow => res[1].1 += 1,
-			Token::Empty => res[2].1 += 1,
-		};
+fn winner(game: &Game) -> Token {
+	match game.status() {
+		Status::Won(token) => token,
+		_ => Token::Empty,
 	}
-	tx.send(res).unwrap();
 }
 
-// Throws threads that will throw a certain number of rounds
-pub fn game_session(
-	number_thread: u32,
+// How often the threads send the statistics of their last games
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+// Launches a number of rounds between two fictitious players who play random moves,
+// the statistics of the games are given to `report` in batches and the rounds
+// stop early once `stop` is set
+fn rounds<F: FnMut(Stats)>(
+	board: &Board,
 	number_rounds: u64,
 	rand_first_player: bool,
-) -> [(Token, u64); 3] {
-	let total_rounds = number_rounds * number_thread as u64;
-	let mut res = [(Token::Red, 0), (Token::Yellow, 0), (Token::Empty, 0)];
+	seed: u64,
+	stop: &AtomicBool,
+	mut report: F,
+) {
+	let mut rng = StdRng::seed_from_u64(seed);
+	let mut stats = Stats::default();
+	let mut now = Instant::now();
+	for _i in 0..number_rounds {
+		if stop.load(Ordering::Relaxed) {
+			break;
+		}
+		let (first_player, game) = round(board, rand_first_player, &mut rng);
+		let line = game.board().winning_line().map(|line| line.direction);
+		stats.add(first_player, winner(&game), &game.board().history()[board.history().len()..], line);
 
-	let now = Instant::now();
+		let elapsed = now.elapsed();
+		if elapsed >= REPORT_INTERVAL {
+			stats.elapsed = elapsed;
+			report(std::mem::take(&mut stats));
+			now = Instant::now();
+		}
+	}
+	stats.elapsed = now.elapsed();
+	report(stats);
+}
+
+// The threads launched by `fan_out`
+pub struct Workers(Vec<JoinHandle<()>>);
+
+impl Workers {
+	// Waits for the end of every thread, the panic of a thread goes on in the
+	// calling one so that its results are not silently lost
+	pub fn join(self) {
+		for handle in self.0 {
+			if let Err(panic) = handle.join() {
+				std::panic::resume_unwind(panic);
+			}
+		}
+	}
+}
 
-	let mut children = vec![];
+// Runs `job` in `number_thread` threads, each one gets the index of its thread
+// and sends its results through the channel returned. The channel is closed once
+// every thread has finished, the threads must then be joined.
+pub fn fan_out<T, F>(number_thread: u32, job: F) -> (Receiver<T>, Workers)
+where
+	T: Send + 'static,
+	F: Fn(u32, Sender<T>) + Send + Sync + 'static,
+{
+	let job = Arc::new(job);
 	let (tx, rx) = std::sync::mpsc::channel();
-	for _i in 0..number_thread {
+	let mut handles = Vec::with_capacity(number_thread as usize);
+	for thread in 0..number_thread {
 		let tx_copy = std::sync::mpsc::Sender::clone(&tx);
-		children.push(std::thread::spawn(move || {
-			rounds(number_rounds, tx_copy, rand_first_player)
-		}));
+		let job = Arc::clone(&job);
+		handles.push(std::thread::spawn(move || job(thread, tx_copy)));
 	}
-	std::mem::drop(tx);
-	for tmp in rx {
-		res[0].1 += tmp[0].1;
-		res[1].1 += tmp[1].1;
-		res[2].1 += tmp[2].1;
+	(rx, Workers(handles))
+}
+
+// Derives `number` seeds from `seed`, one for each thread or player using its own generator
+pub fn derive_seeds(seed: u64, number: usize) -> Vec<u64> {
+	let mut rng = StdRng::seed_from_u64(seed);
+	(0..number).map(|_| rng.gen()).collect()
+}
+
+// Throws threads that will throw a certain number of rounds, each thread plays
+// with its own seed derived from `seed` so that the results only depend on it
+// whatever the order in which the threads run. A random seed is drawn without one.
+// `progress` is called regularly with the games played so far, the threads stop
+// once `stop` is set and the session holds the games played until then.
+pub fn game_session<F: FnMut(&Progress)>(
+	board: &Board,
+	number_thread: u32,
+	number_rounds: u64,
+	rand_first_player: bool,
+	seed: Option<u64>,
+	stop: Arc<AtomicBool>,
+	mut progress: F,
+) -> Session {
+	let now = Instant::now();
+
+	let seed = seed.unwrap_or_else(rand::random);
+	let seeds = derive_seeds(seed, number_thread as usize);
+	let board = board.clone();
+	let (rx, workers) = fan_out(number_thread, move |thread, tx| {
+		rounds(&board, number_rounds, rand_first_player, seeds[thread as usize], &stop, |stats| {
+			tx.send((thread, stats)).unwrap()
+		});
+	});
+
+	let mut threads = vec![Stats::default(); number_thread as usize];
+	let total = |threads: &[Stats]| {
+		let mut total = Stats::default();
+		threads.iter().for_each(|stats| total += stats);
+		total
+	};
+	let mut reported = Instant::now();
+	loop {
+		match rx.recv_timeout(REPORT_INTERVAL) {
+			Ok((thread, stats)) => threads[thread as usize] += &stats,
+			Err(RecvTimeoutError::Timeout) => {}
+			Err(RecvTimeoutError::Disconnected) => break,
+		}
+		if reported.elapsed() >= REPORT_INTERVAL {
+			progress(&Progress {
+				stats: total(&threads),
+				games: number_rounds.saturating_mul(number_thread as u64),
+				elapsed: now.elapsed(),
+			});
+			reported = Instant::now();
+		}
+	}
+	workers.join();
+
+	Session {
+		seed,
+		interrupted: total(&threads).games() < number_rounds.saturating_mul(number_thread as u64),
+		threads,
+		elapsed: now.elapsed(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{derive_seeds, fan_out, game_session, round, AtomicBool, Arc, Board, Ordering, StdRng};
+	use rand::SeedableRng;
+
+	#[test]
+	fn same_seed_same_rounds() {
+		let winners = |seed| {
+			let mut rng = StdRng::seed_from_u64(seed);
+			(0..50)
+				.map(|_| {
+					let (first_player, game) = round(&Board::new(), true, &mut rng);
+					(first_player, game.board().history().to_vec())
+				})
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(winners(3), winners(3));
+		assert_ne!(winners(3), winners(4));
+		assert_eq!(derive_seeds(3, 4), derive_seeds(3, 4));
+		assert_eq!(derive_seeds(3, 4)[..2], derive_seeds(3, 2)[..]);
+	}
+
+	#[test]
+	#[should_panic(expected = "worker failed")]
+	fn worker_panics_are_passed_on() {
+		let (rx, workers) = fan_out(2, |thread, tx| {
+			if thread == 1 {
+				panic!("worker failed");
+			}
+			tx.send(thread).unwrap();
+		});
+		assert_eq!(rx.iter().collect::<Vec<_>>(), vec![0]);
+		workers.join();
+	}
+
+	#[test]
+	fn stopped_session_keeps_the_games_played() {
+		let board = Board::with_size(5, 4, 3).unwrap();
+		let session = |stop: bool| game_session(&board, 2, 300, true, Some(9), Arc::new(AtomicBool::new(stop)), |_| {});
+		let (first, second) = (session(false), session(false));
+		assert!(!first.interrupted);
+		assert_eq!(first.total().games(), 600);
+		assert_eq!(first.total().results, second.total().results);
+		assert_eq!(first.total().lengths, second.total().lengths);
+
+		let stopped = session(true);
+		assert!(stopped.interrupted);
+		assert_eq!(stopped.total().games(), 0);
+
+		// the workers stop in the middle of the session
+		let stop = Arc::new(AtomicBool::new(false));
+		let flag = Arc::clone(&stop);
+		let mut reports = 0;
+		let partial = game_session(&board, 1, u64::MAX, true, None, stop, |progress| {
+			reports += 1;
+			assert!(progress.stats.games() <= progress.games);
+			flag.store(true, Ordering::Relaxed);
+		});
+		assert!(partial.interrupted && partial.total().games() > 0);
+		assert!(reports >= 1);
 	}
-	let exec_time = now.elapsed().as_millis();
-	println!(
-		"finished after {} milliseconds or {:.2} seconds or {:.2} minutes.",
-		exec_time,
-		exec_time as f64 / 1000.0,
-		exec_time as f64 / 1000.0 / 60.0
-	);
-	println!(
-		"\nresult of {} {} : ",
-		total_rounds,
-		if total_rounds <= 1 { "game" } else { "games" }
-	);
-	println!(
-		"\t{:.3}% victory for the red token.",
-		(res[0].1 as f64) * 100.0 / (total_rounds as f64)
-	);
-	println!(
-		"\t{:.3}% victory for the yellow token.",
-		(res[1].1 as f64) * 100.0 / (total_rounds as f64)
-	);
-	println!(
-		"\t{:.3}% draw.",
-		(res[2].1 as f64) * 100.0 / (total_rounds as f64)
-	);
-	res
 }
@@ -0,0 +1,263 @@
+use std::fmt;
+
+use crossterm::style::{Attribute, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+
+use super::board::{Board, Token};
+use super::theme::Theme;
+
+// Number of characters written on the left of the board
+pub const MARGIN: usize = 8;
+
+// Turns the board and the names of the players into text. The boards of every
+// renderer have the same layout so that a cell is found at the same place:
+// the board starts after a margin of `MARGIN` characters, each column takes 4
+// characters, a line separates the column numbers and each row of cells.
+pub trait Renderer {
+	// The lines of the board, from the line above the column numbers
+	fn lines(&self, board: &Board) -> Vec<String>;
+
+	// A cell of the board holding `token`, an empty one is a hole
+	fn cell(&self, token: Token) -> String;
+
+	// The `name` of the tokens of the player, written so that they are told apart
+	fn name(&self, token: Token, name: &str) -> String;
+
+	// A message about something that went wrong
+	fn error(&self, text: &str) -> String;
+
+	fn render(&self, board: &Board) -> String {
+		self.lines(board).join("\n")
+	}
+}
+
+// The cells of the winning line, if any
+fn winning_cells(board: &Board) -> Vec<(usize, usize)> {
+	board.winning_line().map(|line| line.cells).unwrap_or_default()
+}
+
+// Colored cells on a terminal that understands the ANSI escape sequences
+pub struct Ansi {
+	pub theme: Theme,
+}
+
+impl Renderer for Ansi {
+	fn lines(&self, board: &Board) -> Vec<String> {
+		let theme = &self.theme;
+		let margin = " ".repeat(MARGIN);
+		let separation_line = format!(
+			"{}{}{}{}",
+			margin,
+			SetBackgroundColor(theme.frame),
+			" ".repeat(1 + 4 * board.cols()),
+			ResetColor
+		);
+		let mut lines = vec![separation_line.clone()];
+
+		// First row to display column numbers
+		let mut numbers = format!(
+			"{:<width$}{} {}",
+			"column :",
+			SetBackgroundColor(theme.frame),
+			ResetColor,
+			width = MARGIN
+		);
+		for i in 1..=board.cols() {
+			numbers += &format!(
+				"{}{}{:^3}{} {}",
+				SetBackgroundColor(theme.header),
+				SetForegroundColor(theme.numbers),
+				i,
+				SetBackgroundColor(theme.frame),
+				ResetColor
+			);
+		}
+		lines.push(numbers);
+		lines.push(separation_line.clone());
+
+		// The cells of the winning line blink with a marker in them
+		let winning_cells = winning_cells(board);
+		for x in 0..board.rows() {
+			let mut line = format!("{}{} {}", margin, SetBackgroundColor(theme.frame), ResetColor);
+			for y in 0..board.cols() {
+				let token = board.cell(x, y).unwrap();
+				if winning_cells.contains(&(x, y)) {
+					let symbol = match theme.symbol(token) {
+						' ' => '*',
+						symbol => symbol,
+					};
+					line += &format!(
+						"{}{}{} {} {}{}",
+						SetBackgroundColor(theme.color(token)),
+						SetForegroundColor(theme.mark),
+						SetAttribute(Attribute::SlowBlink),
+						symbol,
+						SetAttribute(Attribute::Reset),
+						ResetColor
+					);
+				} else {
+					line += &self.cell(token);
+				}
+				line += &format!("{} {}", SetBackgroundColor(theme.frame), ResetColor);
+			}
+			lines.push(line);
+			lines.push(separation_line.clone());
+		}
+		lines
+	}
+
+	fn cell(&self, token: Token) -> String {
+		format!(
+			"{}{} {} {}",
+			SetBackgroundColor(self.theme.color(token)),
+			SetForegroundColor(self.theme.mark),
+			self.theme.symbol(token),
+			ResetColor
+		)
+	}
+
+	// The name in the color of the player, followed by their symbol if any
+	fn name(&self, token: Token, name: &str) -> String {
+		let colored = format!("{}{}{}", SetForegroundColor(self.theme.text(token)), name, ResetColor);
+		match self.theme.symbol(token) {
+			' ' => colored,
+			symbol => format!("{} ({})", colored, symbol),
+		}
+	}
+
+	fn error(&self, text: &str) -> String {
+		format!("{}{}{}", SetForegroundColor(self.theme.error), text, ResetColor)
+	}
+}
+
+// Text without any color nor escape sequence: the tokens are written with their
+// symbol, the empty cells with a dot and the tokens of the winning line between
+// brackets
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Plain {
+	pub red: char,
+	pub yellow: char,
+}
+
+impl Default for Plain {
+	fn default() -> Plain {
+		Plain { red: 'X', yellow: 'O' }
+	}
+}
+
+impl Plain {
+	// The symbols of the theme, X and O when it has none
+	pub fn with_theme(theme: &Theme) -> Plain {
+		let default = Plain::default();
+		let symbol = |symbol: char, default: char| if symbol == ' ' { default } else { symbol };
+		Plain {
+			red: symbol(theme.red_symbol, default.red),
+			yellow: symbol(theme.yellow_symbol, default.yellow),
+		}
+	}
+
+	fn symbol(&self, token: Token) -> char {
+		match token {
+			Token::Red => self.red,
+			Token::Yellow => self.yellow,
+			Token::Empty => '.',
+		}
+	}
+}
+
+impl Renderer for Plain {
+	fn lines(&self, board: &Board) -> Vec<String> {
+		let margin = " ".repeat(MARGIN);
+		let separation_line = format!("{}+{}", margin, "---+".repeat(board.cols()));
+		let mut lines = vec![separation_line.clone()];
+
+		let mut numbers = format!("{:<width$}|", "column :", width = MARGIN);
+		for i in 1..=board.cols() {
+			numbers += &format!("{:^3}|", i);
+		}
+		lines.push(numbers);
+		lines.push(separation_line.clone());
+
+		let winning_cells = winning_cells(board);
+		for x in 0..board.rows() {
+			let mut line = format!("{}|", margin);
+			for y in 0..board.cols() {
+				let symbol = self.symbol(board.cell(x, y).unwrap());
+				if winning_cells.contains(&(x, y)) {
+					line += &format!("[{}]|", symbol);
+				} else {
+					line += &format!(" {} |", symbol);
+				}
+			}
+			lines.push(line);
+			lines.push(separation_line.clone());
+		}
+		lines
+	}
+
+	fn cell(&self, token: Token) -> String {
+		format!(" {} ", self.symbol(token))
+	}
+
+	fn name(&self, token: Token, name: &str) -> String {
+		format!("{} ({})", name, self.symbol(token))
+	}
+
+	fn error(&self, text: &str) -> String {
+		text.to_string()
+	}
+}
+
+// The board written by the plain renderer
+impl fmt::Display for Board {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", Plain::default().render(self))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Ansi, Board, Plain, Renderer, Theme, Token::*};
+
+	#[test]
+	fn plain_board() {
+		let mut board = Board::with_size(3, 2, 2).unwrap();
+		board.player_stroke(Red, 0).unwrap();
+		board.player_stroke(Yellow, 2).unwrap();
+		assert_eq!(
+			board.to_string(),
+			"        +---+---+---+\n\
+			column :| 1 | 2 | 3 |\n\
+			\x20       +---+---+---+\n\
+			\x20       | . | . | . |\n\
+			\x20       +---+---+---+\n\
+			\x20       | X | . | O |\n\
+			\x20       +---+---+---+"
+		);
+		board.player_stroke(Red, 1).unwrap();
+		assert_eq!(Plain::default().lines(&board)[5], "        |[X]|[X]| O |");
+		assert_eq!(Plain { red: '●', yellow: '○' }.lines(&board)[5], "        |[●]|[●]| ○ |");
+		assert_eq!(Plain::default().name(Yellow, "yellow token"), "yellow token (O)");
+	}
+
+	#[test]
+	fn every_renderer_has_the_same_layout() {
+		let board: Board = "4453".parse().unwrap();
+		let renderers: [&dyn Renderer; 3] = [
+			&Plain::default(),
+			&Ansi { theme: Theme::default() },
+			&Ansi {
+				theme: Theme::MONOCHROME,
+			},
+		];
+		for renderer in renderers {
+			assert_eq!(renderer.lines(&board).len(), 3 + 2 * board.rows());
+			assert_eq!(renderer.render(&board).lines().count(), 3 + 2 * board.rows());
+		}
+		assert!(Ansi {
+			theme: Theme::MONOCHROME
+		}
+		.lines(&board)[13]
+		.contains(" O "));
+		assert_eq!(Plain::with_theme(&Theme::CLASSIC), Plain::default());
+	}
+}
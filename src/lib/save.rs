@@ -0,0 +1,263 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::board::{Board, SizeError, Token};
+use super::notation::{parse_notation, NotationError};
+use super::player::Agent;
+use super::state::Game;
+
+// Version of the save files written by this program, the files of another
+// version are refused rather than misread.
+//
+// A save file holds one `key value` pair per line after its header:
+//
+//     connect-four save 1
+//     red human
+//     yellow minimax:5
+//     cols 7
+//     rows 6
+//     connect 4
+//     turn red
+//     moves 4453
+//
+// The players are written like the `--red` and `--yellow` options of the play
+// subcommand.
+const VERSION: u32 = 1;
+const HEADER: &str = "connect-four save";
+
+#[derive(Debug)]
+pub enum SaveError {
+	Io(io::Error),
+	// The file does not start with the header of a save file
+	NotASave,
+	// The file was written by another version of the program
+	Version(u32),
+	// A line is not a known `key value` pair
+	InvalidLine(String),
+	// A required key is missing
+	Missing(&'static str),
+	// The value of the key cannot be read
+	InvalidValue(&'static str),
+	// The player about to play does not match the moves
+	Turn,
+	// One of the players has no description to write in the file
+	Players,
+	Size(SizeError),
+	Notation(NotationError),
+}
+
+impl fmt::Display for SaveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SaveError::Io(err) => write!(f, "{}", err),
+			SaveError::NotASave => write!(f, "the file is not a saved game"),
+			SaveError::Version(version) => write!(f, "the version {} of the save files is not supported", version),
+			SaveError::InvalidLine(line) => write!(f, "unexpected line '{}'", line),
+			SaveError::Missing(key) => write!(f, "the {} is missing", key),
+			SaveError::InvalidValue(key) => write!(f, "the {} cannot be read", key),
+			SaveError::Turn => write!(f, "the player about to play does not match the moves"),
+			SaveError::Players => write!(f, "one of the players cannot be saved"),
+			SaveError::Size(err) => write!(f, "{}", err),
+			SaveError::Notation(err) => write!(f, "{}", err),
+		}
+	}
+}
+
+impl std::error::Error for SaveError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			SaveError::Io(err) => Some(err),
+			SaveError::Size(err) => Some(err),
+			SaveError::Notation(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for SaveError {
+	fn from(err: io::Error) -> SaveError {
+		SaveError::Io(err)
+	}
+}
+
+impl From<SizeError> for SaveError {
+	fn from(err: SizeError) -> SaveError {
+		SaveError::Size(err)
+	}
+}
+
+impl From<NotationError> for SaveError {
+	fn from(err: NotationError) -> SaveError {
+		SaveError::Notation(err)
+	}
+}
+
+// An interactive game with the players needed to resume it
+#[derive(Clone, Debug)]
+pub struct SavedGame {
+	pub red: Agent,
+	pub yellow: Agent,
+	pub game: Game,
+}
+
+impl SavedGame {
+	pub fn new(red: Agent, yellow: Agent, game: Game) -> SavedGame {
+		SavedGame { red, yellow, game }
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+		fs::write(path, self.to_string())?;
+		Ok(())
+	}
+
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<SavedGame, SaveError> {
+		fs::read_to_string(path)?.parse()
+	}
+}
+
+impl fmt::Display for SavedGame {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let board = self.game.board();
+		writeln!(f, "{} {}", HEADER, VERSION)?;
+		writeln!(f, "red {}\nyellow {}", self.red, self.yellow)?;
+		writeln!(f, "cols {}\nrows {}\nconnect {}", board.cols(), board.rows(), board.connect())?;
+		let turn = if self.game.turn() == Token::Yellow { "yellow" } else { "red" };
+		writeln!(f, "turn {}\nmoves {}", turn, board.to_notation())
+	}
+}
+
+fn parse_value<T: FromStr>(value: Option<&str>, key: &'static str) -> Result<T, SaveError> {
+	value
+		.ok_or(SaveError::Missing(key))?
+		.parse()
+		.map_err(|_| SaveError::InvalidValue(key))
+}
+
+// The values read in a save file
+#[derive(Default)]
+struct Keys<'a> {
+	red: Option<&'a str>,
+	yellow: Option<&'a str>,
+	cols: Option<&'a str>,
+	rows: Option<&'a str>,
+	connect: Option<&'a str>,
+	turn: Option<&'a str>,
+	moves: Option<&'a str>,
+}
+
+impl FromStr for SavedGame {
+	type Err = SaveError;
+
+	fn from_str(content: &str) -> Result<SavedGame, SaveError> {
+		let mut lines = content.lines().map(str::trim).filter(|line| !line.is_empty());
+		let version = lines
+			.next()
+			.and_then(|header| header.strip_prefix(HEADER))
+			.ok_or(SaveError::NotASave)?;
+		let version: u32 = version.trim().parse().map_err(|_| SaveError::NotASave)?;
+		if version != VERSION {
+			return Err(SaveError::Version(version));
+		}
+
+		let mut keys = Keys::default();
+		for line in lines {
+			let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+			let value = Some(value.trim());
+			match key {
+				"red" => keys.red = value,
+				"yellow" => keys.yellow = value,
+				"cols" => keys.cols = value,
+				"rows" => keys.rows = value,
+				"connect" => keys.connect = value,
+				"turn" => keys.turn = value,
+				"moves" => keys.moves = value,
+				_ => return Err(SaveError::InvalidLine(line.to_string())),
+			}
+		}
+
+		let red = parse_value(keys.red, "red")?;
+		let yellow = parse_value(keys.yellow, "yellow")?;
+		let board = Board::with_size(
+			parse_value(keys.cols, "cols")?,
+			parse_value(keys.rows, "rows")?,
+			parse_value(keys.connect, "connect")?,
+		)?;
+		let game = parse_notation(board, keys.moves.ok_or(SaveError::Missing("moves"))?)?;
+		let turn = match keys.turn {
+			Some("red") => Token::Red,
+			Some("yellow") => Token::Yellow,
+			Some(_) => return Err(SaveError::InvalidValue("turn")),
+			None => return Err(SaveError::Missing("turn")),
+		};
+		if turn != game.turn() {
+			return Err(SaveError::Turn);
+		}
+		Ok(SavedGame { red, yellow, game })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_notation, Agent, Board, SaveError, SavedGame};
+
+	#[test]
+	fn round_trip() {
+		let game = parse_notation(Board::with_size(8, 7, 5).unwrap(), "4453").unwrap();
+		let saved = SavedGame::new(Agent::Human, Agent::Minimax { depth: 3 }, game);
+		let content = saved.to_string();
+		assert_eq!(
+			content,
+			"connect-four save 1\nred human\nyellow minimax:3\ncols 8\nrows 7\nconnect 5\nturn red\nmoves 4453\n"
+		);
+
+		let loaded: SavedGame = content.parse().unwrap();
+		assert_eq!((loaded.red, loaded.yellow), (Agent::Human, Agent::Minimax { depth: 3 }));
+		assert_eq!(loaded.game.board().history(), &[3, 3, 4, 2]);
+		assert_eq!(loaded.game.board().connect(), 5);
+	}
+
+	#[test]
+	fn wide_board() {
+		let game = parse_notation(Board::with_size(40, 2, 4).unwrap(), "40,36,1").unwrap();
+		let saved = SavedGame::new(Agent::Human, Agent::Human, game);
+		let loaded: SavedGame = saved.to_string().parse().unwrap();
+		assert_eq!(loaded.game.board().history(), &[39, 35, 0]);
+		assert_eq!(loaded.game.turn(), saved.game.turn());
+	}
+
+	#[test]
+	fn monte_carlo_settings() {
+		let mcts: Agent = "mcts:500:0.75:250".parse().unwrap();
+		let saved = SavedGame::new(mcts, Agent::Random, parse_notation(Board::new(), "").unwrap());
+		let loaded: SavedGame = saved.to_string().parse().unwrap();
+		assert_eq!((loaded.red, loaded.yellow), (mcts, Agent::Random));
+	}
+
+	#[test]
+	fn invalid_files() {
+		let parse = |content: &str| content.parse::<SavedGame>().unwrap_err();
+		assert!(matches!(parse("4453"), SaveError::NotASave));
+		assert!(matches!(parse("connect-four save 2\nred human"), SaveError::Version(2)));
+		assert!(matches!(
+			parse("connect-four save 1\nred human\ncols 7\nrows 6\nconnect 4\nturn red\nmoves "),
+			SaveError::Missing("yellow")
+		));
+		assert!(matches!(
+			parse("connect-four save 1\nred human\nyellow robot\ncols 7\nrows 6\nconnect 4\nturn red\nmoves "),
+			SaveError::InvalidValue("yellow")
+		));
+		assert!(matches!(
+			parse("connect-four save 1\nred human\nyellow human\ncols 7\nrows 6\nconnect 4\nturn red\nmoves 4"),
+			SaveError::Turn
+		));
+		assert!(matches!(
+			parse("connect-four save 1\nred human\nyellow human\ncols 7\nrows 6\nconnect 4\nturn red\nmoves 48"),
+			SaveError::Notation(_)
+		));
+		assert!(matches!(parse("connect-four save 1\ncolour red"), SaveError::InvalidLine(_)));
+		assert!(matches!(parse("connect-four save 1\nmode human"), SaveError::InvalidLine(_)));
+	}
+}
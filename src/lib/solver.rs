@@ -0,0 +1,398 @@
+use super::board::{Board, Token};
+use super::notation::{parse_notation, NotationError};
+use super::state::Status;
+
+// Largest number of entries of the transposition table, a prime number spreads the keys better
+const TABLE_SIZE: usize = (1 << 21) - 9;
+
+// Boards with `size` cells have fewer than 3^size positions, the table of the
+// small boards does not need more entries than that
+fn table_size(size: usize) -> usize {
+	3usize.checked_pow(size as u32).map_or(TABLE_SIZE, |positions| positions.min(TABLE_SIZE))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+	Win,
+	Loss,
+	Draw,
+}
+
+// Value of a position under perfect play for the player about to move
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Solution {
+	// The player about to move
+	pub player: Token,
+	pub outcome: Outcome,
+	// Number of tokens still played before the end of the game
+	pub moves: usize,
+	// Positive when the player to move wins, the quicker the win the higher the score.
+	// A win with the last token of the board is worth 1 and each earlier move adds one
+	// point for the winner.
+	pub score: i32,
+}
+
+// Position seen from the player about to move
+#[derive(Clone, Copy)]
+struct Position {
+	current: u128,
+	mask: u128,
+	moves: usize,
+}
+
+// Bitboard masks that depend on the size of the board, see the layout described on `Board`
+#[derive(Clone, PartialEq)]
+struct Geometry {
+	cols: usize,
+	rows: usize,
+	connect: usize,
+	directions: [usize; 4],
+	size: usize,
+	// bit of the bottom cell of every column
+	bottom: u128,
+	// bit of every cell of the board, the extra bit on top of each column excluded
+	cells: u128,
+}
+
+impl Geometry {
+	fn new(board: &Board) -> Geometry {
+		let column = (1 << board.rows()) - 1;
+		let bottom = (0..board.cols()).fold(0, |mask, col| mask | 1 << (col * board.column_bits()));
+		Geometry {
+			cols: board.cols(),
+			rows: board.rows(),
+			connect: board.connect(),
+			directions: board.directions(),
+			size: board.cols() * board.rows(),
+			bottom,
+			cells: bottom * column,
+		}
+	}
+
+	fn column_mask(&self, col: usize) -> u128 {
+		((1 << self.rows) - 1) << (col * (self.rows + 1))
+	}
+
+	// Empty cells that would complete a line for the tokens of `position`
+	fn winning_cells(&self, position: u128, mask: u128) -> u128 {
+		// the lines of the usual sizes fit on the stack
+		if self.connect <= 8 {
+			self.winning_cells_with(position, mask, &mut [0; 16])
+		} else {
+			self.winning_cells_with(position, mask, &mut vec![0; 2 * self.connect])
+		}
+	}
+
+	fn winning_cells_with(&self, position: u128, mask: u128, lines: &mut [u128]) -> u128 {
+		let connect = self.connect;
+		let (before, after) = lines.split_at_mut(connect);
+		before[0] = u128::MAX;
+		after[0] = u128::MAX;
+
+		// vertical lines can only be completed from the top
+		let mut cells = (1..connect).fold(u128::MAX, |line, k| line & position.checked_shl(k as u32).unwrap_or(0));
+		for &shift in self.directions[1..].iter() {
+			// cells with `k` aligned tokens right before them, and right after them
+			for k in 1..connect {
+				let bits = (k * shift) as u32;
+				before[k] = before[k - 1] & position.checked_shl(bits).unwrap_or(0);
+				after[k] = after[k - 1] & position.checked_shr(bits).unwrap_or(0);
+			}
+			for k in 0..connect {
+				cells |= before[k] & after[connect - 1 - k];
+			}
+		}
+		cells & (self.cells ^ mask)
+	}
+
+	// Unique for each position since the bottom mask marks the height of every column
+	fn key(&self, position: &Position) -> u128 {
+		position.current + position.mask + self.bottom
+	}
+
+	fn possible(&self, position: &Position) -> u128 {
+		(position.mask + self.bottom) & self.cells
+	}
+
+	fn can_win_next(&self, position: &Position) -> bool {
+		self.winning_cells(position.current, position.mask) & self.possible(position) != 0
+	}
+
+	// Playable cells that do not give the opponent an immediate win, empty when
+	// the opponent has a threat that cannot be blocked
+	fn non_losing_moves(&self, position: &Position) -> u128 {
+		let mut possible = self.possible(position);
+		let threats = self.winning_cells(position.current ^ position.mask, position.mask);
+		let forced = possible & threats;
+		if forced != 0 {
+			if forced & (forced - 1) != 0 {
+				return 0;
+			}
+			possible = forced;
+		}
+		possible & !(threats >> 1)
+	}
+
+	// Number of threats created by playing `cell`, used to sort the moves
+	fn move_score(&self, position: &Position, cell: u128) -> u32 {
+		self.winning_cells(position.current | cell, position.mask).count_ones()
+	}
+}
+
+impl Position {
+	fn new(board: &Board, token: Token) -> Position {
+		let (red, yellow) = board.bitboards();
+		Position {
+			current: if token == Token::Red { red } else { yellow },
+			mask: red | yellow,
+			moves: board.moves(),
+		}
+	}
+
+	fn play(&mut self, cell: u128) {
+		self.current ^= self.mask;
+		self.mask |= cell;
+		self.moves += 1;
+	}
+}
+
+// Perfect-play solver, the transposition table is allocated by the first call
+// to `solve` and kept between two calls as long as the size of the board does not change
+#[derive(Default)]
+pub struct Solver {
+	// Each entry keeps the key of the position and an upper bound of its score
+	table: Vec<(u128, i8)>,
+	geometry: Option<Geometry>,
+	order: Vec<usize>,
+	pub explored: u64,
+}
+
+impl Solver {
+	pub fn new() -> Solver {
+		Solver {
+			table: Vec::new(),
+			geometry: None,
+			order: Vec::new(),
+			explored: 0,
+		}
+	}
+
+	// Negamax search with alpha-beta pruning, the current player must not be able to win with its next move
+	fn negamax(&mut self, geometry: &Geometry, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+		self.explored += 1;
+		let size = geometry.size;
+
+		let next = geometry.non_losing_moves(position);
+		if next == 0 {
+			return -((size - position.moves) as i32) / 2;
+		}
+		if position.moves + 2 >= size {
+			return 0;
+		}
+
+		// the opponent cannot win with its next move
+		let min = -((size - 2 - position.moves) as i32) / 2;
+		if alpha < min {
+			alpha = min;
+			if alpha >= beta {
+				return alpha;
+			}
+		}
+
+		// we cannot win with our next move
+		let key = geometry.key(position);
+		let index = ((key as u64 ^ (key >> 64) as u64) % self.table.len() as u64) as usize;
+		let max = if self.table[index].0 == key {
+			self.table[index].1 as i32
+		} else {
+			((size - 1 - position.moves) as i32) / 2
+		};
+		if beta > max {
+			beta = max;
+			if alpha >= beta {
+				return beta;
+			}
+		}
+
+		// stable insertion sort on the number of threats, columns start from the center
+		let mut moves: Vec<(u128, u32)> = Vec::with_capacity(geometry.cols);
+		for &col in self.order.iter() {
+			let cell = next & geometry.column_mask(col);
+			if cell != 0 {
+				let score = geometry.move_score(position, cell);
+				let at = moves.iter().position(|&(_, s)| s < score).unwrap_or(moves.len());
+				moves.insert(at, (cell, score));
+			}
+		}
+
+		for (cell, _) in moves {
+			let mut child = *position;
+			child.play(cell);
+			let score = -self.negamax(geometry, &child, -beta, -alpha);
+			if score >= beta {
+				return score;
+			}
+			if score > alpha {
+				alpha = score;
+			}
+		}
+
+		self.table[index] = (key, alpha as i8);
+		alpha
+	}
+
+	// Score of the position for the player about to move
+	fn score(&mut self, geometry: &Geometry, position: &Position) -> i32 {
+		let size = geometry.size;
+		if geometry.can_win_next(position) {
+			return (size + 1 - position.moves) as i32 / 2;
+		}
+		let mut min = -((size - position.moves) as i32) / 2;
+		let mut max = (size + 1 - position.moves) as i32 / 2;
+
+		// iterative deepening with null windows, the searches start close to the
+		// end of the game where the positions are quick to solve
+		while min < max {
+			let mut med = min + (max - min) / 2;
+			if med <= 0 && min / 2 < med {
+				med = min / 2;
+			} else if med >= 0 && max / 2 > med {
+				med = max / 2;
+			}
+			let r = self.negamax(geometry, position, med, med + 1);
+			if r <= med {
+				max = r;
+			} else {
+				min = r;
+			}
+		}
+		min
+	}
+
+	// Solves the position of `board` where `token` is about to play
+	pub fn solve(&mut self, board: &Board, token: Token) -> Solution {
+		let geometry = Geometry::new(board);
+		if self.geometry.as_ref() != Some(&geometry) {
+			self.table.clear();
+			self.table.resize(table_size(geometry.size), (0, 0));
+			self.order = board.center_order();
+			self.geometry = Some(geometry.clone());
+		}
+		let size = geometry.size;
+		let position = Position::new(board, token);
+		let score = self.score(&geometry, &position);
+
+		// the winning token is played on a board of `size + 1 - 2 * score` tokens
+		// or one less, the parity tells which player plays it
+		let end = |winner_moves: usize| {
+			let last = size + 1 - 2 * score.unsigned_abs() as usize;
+			if last % 2 == winner_moves % 2 {
+				last
+			} else {
+				last - 1
+			}
+		};
+		match score {
+			0 => Solution {
+				player: token,
+				outcome: Outcome::Draw,
+				moves: size - position.moves,
+				score,
+			},
+			s if s > 0 => Solution {
+				player: token,
+				outcome: Outcome::Win,
+				moves: end(position.moves) - position.moves + 1,
+				score,
+			},
+			_ => Solution {
+				player: token,
+				outcome: Outcome::Loss,
+				moves: end(position.moves + 1) - position.moves + 1,
+				score,
+			},
+		}
+	}
+}
+
+// Returns the game-theoretic value of a position given in one of the notations,
+// a sequence of columns is played on an empty `board`
+pub fn solve(board: Board, position: &str) -> Result<Solution, NotationError> {
+	let game = parse_notation(board, position)?;
+	if let Status::Won(_) = game.status() {
+		return Err(NotationError::Decided);
+	}
+	Ok(Solver::new().solve(game.board(), game.turn()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{solve, table_size, Board, NotationError, Outcome::*, Solver, Token::*, TABLE_SIZE};
+
+	#[test]
+	fn invalid_positions() {
+		assert_eq!(solve(Board::new(), "1284"), Err(NotationError::InvalidColumn(2, "8".to_string())));
+		assert_eq!(solve(Board::new(), "1111111"), Err(NotationError::ColumnFull(6)));
+		assert_eq!(solve(Board::new(), "12121213"), Err(NotationError::GameOver(7)));
+		assert_eq!(solve(Board::new(), "1212121"), Err(NotationError::Decided));
+		assert_eq!(solve(Board::new(), "7/7/r6/ry5/ry5/ry5 y"), Err(NotationError::Decided));
+	}
+
+	#[test]
+	fn immediate_win() {
+		let solution = solve(Board::new(), "121212").unwrap();
+		assert_eq!((solution.outcome, solution.moves), (Win, 1));
+	}
+
+	#[test]
+	fn grid_position() {
+		// the position of "27374" where red threatens both ends of its row
+		let solution = solve(Board::new(), "7/7/7/7/6y/1rrr2y y").unwrap();
+		assert_eq!((solution.player, solution.outcome, solution.moves), (Yellow, Loss, 2));
+	}
+
+	#[test]
+	fn unstoppable_double_threat() {
+		let solution = solve(Board::new(), "27374").unwrap();
+		assert_eq!((solution.outcome, solution.moves), (Loss, 2));
+	}
+
+	#[test]
+	fn endgames() {
+		// expected values computed with an exhaustive search
+		let positions = [
+			("3633345554631767444326426762155251", Loss, 2),
+			("2653764717636756453355323165142271", Draw, 8),
+			("4143666431522766112552546171242337", Win, 7),
+			("4511744453423521345613313266721527", Draw, 8),
+			("6725231112111477732532336473527555", Win, 3),
+			("5676775756612325645231273673342112", Win, 3),
+			("3145226346767552763167136217352521", Loss, 6),
+			("2142277244214136757567256454166356", Loss, 4),
+		];
+		for (position, outcome, moves) in positions {
+			let solution = solve(Board::new(), position).unwrap();
+			assert_eq!((solution.outcome, solution.moves), (outcome, moves), "{}", position);
+		}
+	}
+
+	#[test]
+	fn small_boards() {
+		let solution = solve(Board::with_size(4, 3, 3).unwrap(), "").unwrap();
+		assert_eq!((solution.outcome, solution.moves), (Win, 9));
+		let solution = solve(Board::with_size(5, 4, 4).unwrap(), "").unwrap();
+		assert_eq!(solution.outcome, Draw);
+	}
+
+	#[test]
+	fn table_sized_to_the_board() {
+		let mut solver = Solver::new();
+		assert!(solver.table.is_empty());
+		solver.solve(&Board::with_size(4, 3, 3).unwrap(), Red);
+		assert_eq!(solver.table.len(), 3usize.pow(12));
+		let board: Board = "3633345554631767444326426762155251".parse().unwrap();
+		solver.solve(&board, Red);
+		assert_eq!(solver.table.len(), TABLE_SIZE);
+		assert_eq!(table_size(128), TABLE_SIZE);
+	}
+}
@@ -0,0 +1,143 @@
+use super::board::{Board, MoveError, Token};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+	InProgress,
+	Won(Token),
+	Drawn,
+}
+
+// A game played on a board, it knows whose turn it is and refuses the moves
+// played out of turn or after the end of the game
+#[derive(Clone, Debug)]
+pub struct Game {
+	board: Board,
+	turn: Token,
+}
+
+impl Game {
+	// The red player starts, unless the board already holds some moves in
+	// which case the player who did not play the last one continues
+	pub fn new(board: Board) -> Game {
+		Game::with_first_player(board, Token::Red)
+	}
+
+	pub fn with_first_player(board: Board, first_player: Token) -> Game {
+		let turn = Game::turn_after(&board, first_player);
+		Game { board, turn }
+	}
+
+	// The player about to play on `board`, `first_player` when no move was played
+	pub fn turn_after(board: &Board, first_player: Token) -> Token {
+		match board.last_move() {
+			Some((_, token)) => token.opponent(),
+			None => first_player,
+		}
+	}
+
+	pub fn board(&self) -> &Board {
+		&self.board
+	}
+
+	// The player about to play
+	pub fn turn(&self) -> Token {
+		self.turn
+	}
+
+	pub fn status(&self) -> Status {
+		match self.board.check_winner() {
+			Token::Empty if self.board.is_full() => Status::Drawn,
+			Token::Empty => Status::InProgress,
+			token => Status::Won(token),
+		}
+	}
+
+	pub fn is_over(&self) -> bool {
+		self.status() != Status::InProgress
+	}
+
+	// Plays the token of `player` in the column `col` (starting from 0)
+	pub fn play(&mut self, player: Token, col: i8) -> Result<(), MoveError> {
+		if self.is_over() {
+			return Err(MoveError::GameOver);
+		}
+		if player != self.turn {
+			return Err(MoveError::WrongTurn);
+		}
+		self.board.player_stroke(player, col)?;
+		self.turn = player.opponent();
+		Ok(())
+	}
+
+	// Takes back the last move, its player is the one about to play again
+	pub fn undo(&mut self) -> Option<(usize, Token)> {
+		let (col, token) = self.board.undo()?;
+		self.turn = token;
+		Some((col, token))
+	}
+
+	pub fn redo(&mut self) -> Option<(usize, Token)> {
+		let (col, token) = self.board.redo()?;
+		self.turn = token.opponent();
+		Some((col, token))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Board, Game, MoveError, Status, Token::*};
+
+	#[test]
+	fn players_take_turns() {
+		let mut game = Game::new(Board::new());
+		assert_eq!(game.turn(), Red);
+		assert_eq!(game.play(Yellow, 3), Err(MoveError::WrongTurn));
+		assert_eq!(game.play(Empty, 3), Err(MoveError::WrongTurn));
+		assert_eq!(game.play(Red, 3), Ok(()));
+		assert_eq!(game.turn(), Yellow);
+		assert_eq!(game.play(Red, 3), Err(MoveError::WrongTurn));
+		assert_eq!(game.play(Yellow, 9), Err(MoveError::OutOfRange));
+		assert_eq!(game.turn(), Yellow);
+
+		let game = Game::with_first_player(Board::new(), Yellow);
+		assert_eq!(game.turn(), Yellow);
+	}
+
+	#[test]
+	fn no_move_after_a_win() {
+		let mut game = Game::new(Board::new());
+		for col in [0, 1, 0, 1, 0, 1] {
+			game.play(game.turn(), col).unwrap();
+		}
+		assert_eq!(game.status(), Status::InProgress);
+		game.play(Red, 0).unwrap();
+		assert_eq!(game.status(), Status::Won(Red));
+		assert_eq!(game.play(Yellow, 1), Err(MoveError::GameOver));
+	}
+
+	#[test]
+	fn drawn_game() {
+		let mut game = Game::new(Board::with_size(3, 2, 3).unwrap());
+		for col in [0, 1, 2, 0, 1] {
+			game.play(game.turn(), col).unwrap();
+			assert_eq!(game.status(), Status::InProgress);
+		}
+		game.play(Yellow, 2).unwrap();
+		assert_eq!(game.status(), Status::Drawn);
+	}
+
+	#[test]
+	fn undo_gives_the_turn_back() {
+		let mut game = Game::new(Board::new());
+		game.play(Red, 2).unwrap();
+		game.play(Yellow, 4).unwrap();
+		assert_eq!(game.undo(), Some((4, Yellow)));
+		assert_eq!(game.turn(), Yellow);
+		assert_eq!(game.redo(), Some((4, Yellow)));
+		assert_eq!(game.turn(), Red);
+
+		// the turn follows the moves already on the board
+		let game = Game::with_first_player(game.board().clone(), Yellow);
+		assert_eq!(game.turn(), Red);
+	}
+}
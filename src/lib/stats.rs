@@ -0,0 +1,573 @@
+use std::fmt;
+use std::fmt::Write;
+use std::ops::AddAssign;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::board::{Direction, Token};
+
+// Number of wins of each color and of draws
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Tally {
+	pub red: u64,
+	pub yellow: u64,
+	pub draws: u64,
+}
+
+impl Tally {
+	pub fn add(&mut self, winner: Token) {
+		match winner {
+			Token::Red => self.red += 1,
+			Token::Yellow => self.yellow += 1,
+			Token::Empty => self.draws += 1,
+		}
+	}
+
+	pub fn games(&self) -> u64 {
+		self.red + self.yellow + self.draws
+	}
+
+	fn to_json(self) -> String {
+		format!(
+			"{{\"games\": {}, \"red\": {}, \"yellow\": {}, \"draws\": {}}}",
+			self.games(),
+			self.red,
+			self.yellow,
+			self.draws
+		)
+	}
+}
+
+impl AddAssign for Tally {
+	fn add_assign(&mut self, other: Tally) {
+		self.red += other.red;
+		self.yellow += other.yellow;
+		self.draws += other.draws;
+	}
+}
+
+// Wins by the direction of the winning line
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Lines {
+	pub horizontal: u64,
+	pub vertical: u64,
+	pub diagonal: u64,
+}
+
+impl Lines {
+	fn add(&mut self, direction: Direction) {
+		match direction {
+			Direction::Horizontal => self.horizontal += 1,
+			Direction::Vertical => self.vertical += 1,
+			Direction::DiagonalDown | Direction::DiagonalUp => self.diagonal += 1,
+		}
+	}
+
+	fn total(&self) -> u64 {
+		self.horizontal + self.vertical + self.diagonal
+	}
+}
+
+impl AddAssign for Lines {
+	fn add_assign(&mut self, other: Lines) {
+		self.horizontal += other.horizontal;
+		self.vertical += other.vertical;
+		self.diagonal += other.diagonal;
+	}
+}
+
+// Adds the counts of `other` to the ones of `counts`, lengthened when needed
+fn add_counts<T: AddAssign + Copy + Default>(counts: &mut Vec<T>, other: &[T]) {
+	if counts.len() < other.len() {
+		counts.resize(other.len(), T::default());
+	}
+	for (count, &other) in counts.iter_mut().zip(other) {
+		*count += other;
+	}
+}
+
+// What happened in the games played by a thread
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Stats {
+	pub results: Tally,
+	// The results of the games started by the red player and by the yellow one
+	pub red_first: Tally,
+	pub yellow_first: Tally,
+	// The number of tokens played in all the games
+	pub moves: u64,
+	// The number of games for each number of tokens played
+	pub lengths: Vec<u64>,
+	// The number of tokens played in each column
+	pub columns: Vec<u64>,
+	// The results of the games by the column of their first move
+	pub first_moves: Vec<Tally>,
+	pub lines: Lines,
+	pub elapsed: Duration,
+}
+
+impl Stats {
+	// Counts a game started by `first_player` in which `moves` were played, the
+	// line of the winner has the `direction` given
+	pub fn add(&mut self, first_player: Token, winner: Token, moves: &[usize], direction: Option<Direction>) {
+		self.results.add(winner);
+		match first_player {
+			Token::Yellow => self.yellow_first.add(winner),
+			_ => self.red_first.add(winner),
+		}
+		self.moves += moves.len() as u64;
+
+		if self.lengths.len() <= moves.len() {
+			self.lengths.resize(moves.len() + 1, 0);
+		}
+		self.lengths[moves.len()] += 1;
+		for &col in moves {
+			if self.columns.len() <= col {
+				self.columns.resize(col + 1, 0);
+			}
+			self.columns[col] += 1;
+		}
+		if let Some(&col) = moves.first() {
+			if self.first_moves.len() <= col {
+				self.first_moves.resize(col + 1, Tally::default());
+			}
+			self.first_moves[col].add(winner);
+		}
+		if let Some(direction) = direction {
+			self.lines.add(direction);
+		}
+	}
+
+	pub fn games(&self) -> u64 {
+		self.results.games()
+	}
+
+	// The average number of tokens played in a game
+	pub fn average_length(&self) -> f64 {
+		if self.games() == 0 {
+			0.0
+		} else {
+			self.moves as f64 / self.games() as f64
+		}
+	}
+
+	pub fn games_per_second(&self) -> f64 {
+		if self.elapsed.is_zero() {
+			0.0
+		} else {
+			self.games() as f64 / self.elapsed.as_secs_f64()
+		}
+	}
+
+	fn to_json(&self) -> String {
+		let join = |counts: &[u64]| counts.iter().map(u64::to_string).collect::<Vec<_>>().join(", ");
+		let first_moves: Vec<String> = self.first_moves.iter().map(|tally| tally.to_json()).collect();
+		format!(
+			"{{\"games\": {}, \"red\": {}, \"yellow\": {}, \"draws\": {}, \"first_player\": {{\"red\": {}, \"yellow\": {}}}, \
+			\"average_length\": {:.3}, \"lengths\": [{}], \"columns\": [{}], \"first_moves\": [{}], \
+			\"lines\": {{\"horizontal\": {}, \"vertical\": {}, \"diagonal\": {}}}, \
+			\"elapsed_ms\": {:.3}, \"games_per_second\": {:.3}}}",
+			self.games(),
+			self.results.red,
+			self.results.yellow,
+			self.results.draws,
+			self.red_first.to_json(),
+			self.yellow_first.to_json(),
+			self.average_length(),
+			join(&self.lengths),
+			join(&self.columns),
+			first_moves.join(", "),
+			self.lines.horizontal,
+			self.lines.vertical,
+			self.lines.diagonal,
+			self.elapsed.as_secs_f64() * 1000.0,
+			self.games_per_second()
+		)
+	}
+
+	// The lengths and the columns are padded with zeros up to `lengths` and `cols`
+	// so that every line has the same fields
+	fn to_csv(&self, name: &str, lengths: usize, cols: usize) -> String {
+		let first_moves = (0..cols).map(|col| self.first_moves.get(col).copied().unwrap_or_default());
+		let tallies: Vec<Tally> = [self.results, self.red_first, self.yellow_first].into_iter().chain(first_moves).collect();
+		let counts: Vec<String> = tallies
+			.iter()
+			.flat_map(|tally| [tally.red, tally.yellow, tally.draws])
+			.chain([self.lines.horizontal, self.lines.vertical, self.lines.diagonal])
+			.chain((0..lengths).map(|length| self.lengths.get(length).copied().unwrap_or(0)))
+			.chain((0..cols).map(|col| self.columns.get(col).copied().unwrap_or(0)))
+			.map(|count| count.to_string())
+			.collect();
+		format!(
+			"{},{},{},{:.3},{:.3},{:.3}",
+			name,
+			self.games(),
+			counts.join(","),
+			self.average_length(),
+			self.elapsed.as_secs_f64() * 1000.0,
+			self.games_per_second()
+		)
+	}
+}
+
+impl AddAssign<&Stats> for Stats {
+	fn add_assign(&mut self, other: &Stats) {
+		self.results += other.results;
+		self.red_first += other.red_first;
+		self.yellow_first += other.yellow_first;
+		self.moves += other.moves;
+		add_counts(&mut self.lengths, &other.lengths);
+		add_counts(&mut self.columns, &other.columns);
+		add_counts(&mut self.first_moves, &other.first_moves);
+		self.lines += other.lines;
+		self.elapsed += other.elapsed;
+	}
+}
+
+// How the results of the random games are printed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+	Text,
+	Json,
+	Csv,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(format: &str) -> Result<Format, String> {
+		match format {
+			"text" => Ok(Format::Text),
+			"json" => Ok(Format::Json),
+			"csv" => Ok(Format::Csv),
+			_ => Err(format!("unknown format {}", format)),
+		}
+	}
+}
+
+// The results of a session of random games
+#[derive(Clone, PartialEq, Debug)]
+pub struct Session {
+	pub seed: u64,
+	// The statistics of each thread, in the order of the threads
+	pub threads: Vec<Stats>,
+	// The time taken by the whole session
+	pub elapsed: Duration,
+	// Whether the session was stopped before all the games were played
+	pub interrupted: bool,
+}
+
+impl Session {
+	// The statistics of all the threads, timed by the whole session
+	pub fn total(&self) -> Stats {
+		let mut total = Stats::default();
+		for stats in &self.threads {
+			total += stats;
+		}
+		total.elapsed = self.elapsed;
+		total
+	}
+
+	pub fn format(&self, format: Format) -> String {
+		match format {
+			Format::Text => self.to_string(),
+			Format::Json => self.to_json(),
+			Format::Csv => self.to_csv(),
+		}
+	}
+
+	pub fn to_json(&self) -> String {
+		let threads: Vec<String> = self.threads.iter().map(Stats::to_json).collect();
+		format!(
+			"{{\"seed\": {}, \"interrupted\": {}, \"total\": {}, \"threads\": [{}]}}\n",
+			self.seed,
+			self.interrupted,
+			self.total().to_json(),
+			threads.join(", ")
+		)
+	}
+
+	// One line for each thread then one for the total, the first player
+	// columns give the results of the games started by this player, the
+	// first_N columns the results of the games opened in the column N, then
+	// come the wins by line, the number of games of each length and the
+	// number of tokens played in each column
+	pub fn to_csv(&self) -> String {
+		let total = self.total();
+		let (lengths, cols) = (total.lengths.len(), total.columns.len().max(total.first_moves.len()));
+		let mut csv = String::from(
+			"thread,games,red,yellow,draws,red_first_red,red_first_yellow,red_first_draws,\
+			yellow_first_red,yellow_first_yellow,yellow_first_draws",
+		);
+		for col in 1..=cols {
+			write!(csv, ",first_{0}_red,first_{0}_yellow,first_{0}_draws", col).unwrap();
+		}
+		csv.push_str(",horizontal,vertical,diagonal");
+		for length in 0..lengths {
+			write!(csv, ",length_{}", length).unwrap();
+		}
+		for col in 1..=cols {
+			write!(csv, ",column_{}", col).unwrap();
+		}
+		csv.push_str(",average_length,elapsed_ms,games_per_second\n");
+		for (thread, stats) in self.threads.iter().enumerate() {
+			writeln!(csv, "{}", stats.to_csv(&thread.to_string(), lengths, cols)).unwrap();
+		}
+		writeln!(csv, "{}", total.to_csv("total", lengths, cols)).unwrap();
+		csv
+	}
+}
+
+impl fmt::Display for Session {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let total = self.total();
+		let games = total.games();
+		let exec_time = self.elapsed.as_millis();
+		// no game may be over yet when the session is interrupted early
+		let percent = |count: u64| if games == 0 { 0.0 } else { count as f64 * 100.0 / games as f64 };
+		if self.interrupted {
+			writeln!(f, "interrupted, only the games already played are counted.")?;
+		}
+		writeln!(
+			f,
+			"finished after {} milliseconds or {:.2} seconds or {:.2} minutes.",
+			exec_time,
+			exec_time as f64 / 1000.0,
+			exec_time as f64 / 1000.0 / 60.0
+		)?;
+		writeln!(
+			f,
+			"\nresult of {} {} with the seed {} : ",
+			games,
+			if games == 1 { "game" } else { "games" },
+			self.seed
+		)?;
+		writeln!(f, "\t{:.3}% victory for the red token.", percent(total.results.red))?;
+		writeln!(f, "\t{:.3}% victory for the yellow token.", percent(total.results.yellow))?;
+		writeln!(f, "\t{:.3}% draw.", percent(total.results.draws))?;
+		writeln!(
+			f,
+			"\nthe games last {:.2} moves on average, {:.0} games were played per second.",
+			total.average_length(),
+			total.games_per_second()
+		)?;
+
+		// the lengths between the shortest and the longest game, the bars are
+		// scaled on the most frequent length
+		let played: Vec<usize> = (0..total.lengths.len()).filter(|&length| total.lengths[length] > 0).collect();
+		if let (Some(&shortest), Some(&longest)) = (played.first(), played.last()) {
+			let most = total.lengths.iter().max().copied().unwrap_or(1);
+			writeln!(f, "\nlength of the games :")?;
+			for length in shortest..=longest {
+				let count = total.lengths[length];
+				let bar = "#".repeat((count * 40 / most) as usize);
+				writeln!(f, "\t{:>3} moves {:>7.3}% {}", length, percent(count), bar)?;
+			}
+		}
+
+		if total.moves > 0 {
+			writeln!(f, "\ntokens played in each column :")?;
+			for (col, &count) in total.columns.iter().enumerate() {
+				writeln!(f, "\tcolumn {:>2} : {:.3}%", col + 1, count as f64 * 100.0 / total.moves as f64)?;
+			}
+		}
+
+		if total.first_moves.iter().any(|tally| tally.games() > 0) {
+			writeln!(f, "\nresults by the column of the first move :")?;
+			for (col, tally) in total.first_moves.iter().enumerate().filter(|(_, tally)| tally.games() > 0) {
+				let share = |count: u64| count as f64 * 100.0 / tally.games() as f64;
+				writeln!(
+					f,
+					"\tcolumn {:>2} : {} {}, {:.3}% red, {:.3}% yellow, {:.3}% draw.",
+					col + 1,
+					tally.games(),
+					if tally.games() <= 1 { "game" } else { "games" },
+					share(tally.red),
+					share(tally.yellow),
+					share(tally.draws)
+				)?;
+			}
+		}
+
+		let wins = total.lines.total();
+		if wins > 0 {
+			let share = |count: u64| count as f64 * 100.0 / wins as f64;
+			writeln!(
+				f,
+				"\nthe winning lines are {:.3}% horizontal, {:.3}% vertical and {:.3}% diagonal.",
+				share(total.lines.horizontal),
+				share(total.lines.vertical),
+				share(total.lines.diagonal)
+			)?;
+		}
+		Ok(())
+	}
+}
+
+// How far a session of random games has gone
+pub struct Progress {
+	// The statistics of the games played so far by all the threads
+	pub stats: Stats,
+	// The number of games the session will play
+	pub games: u64,
+	pub elapsed: Duration,
+}
+
+impl Progress {
+	// The time left at the current pace, unknown before the first game
+	pub fn remaining(&self) -> Option<Duration> {
+		let done = self.stats.games();
+		if done == 0 {
+			None
+		} else {
+			let left = self.games.saturating_sub(done) as f64;
+			Some(self.elapsed.mul_f64(left / done as f64))
+		}
+	}
+}
+
+impl fmt::Display for Progress {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let done = self.stats.games();
+		let percent = |count: u64| if done == 0 { 0.0 } else { count as f64 * 100.0 / done as f64 };
+		let rate = if self.elapsed.is_zero() {
+			0.0
+		} else {
+			done as f64 / self.elapsed.as_secs_f64()
+		};
+		write!(
+			f,
+			"{}/{} games ({:.1}%), {:.0} games/s, ",
+			done,
+			self.games,
+			done as f64 * 100.0 / self.games.max(1) as f64,
+			rate
+		)?;
+		match self.remaining() {
+			Some(remaining) => write!(f, "{}s left", remaining.as_secs())?,
+			None => write!(f, "time left unknown")?,
+		}
+		write!(
+			f,
+			" : red {:.2}%, yellow {:.2}%, draw {:.2}%",
+			percent(self.stats.results.red),
+			percent(self.stats.results.yellow),
+			percent(self.stats.results.draws)
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Direction::*, Duration, Format, Progress, Session, Stats, Token::*};
+
+	fn session() -> Session {
+		let mut first = Stats::default();
+		first.add(Red, Red, &[3, 2, 3, 2, 3, 2, 3], Some(Vertical));
+		first.add(Yellow, Empty, &[0; 42], None);
+		first.elapsed = Duration::from_millis(10);
+		let mut second = Stats::default();
+		second.add(Yellow, Red, &[1; 21], Some(DiagonalUp));
+		second.elapsed = Duration::from_millis(20);
+		Session {
+			seed: 5,
+			threads: vec![first, second],
+			elapsed: Duration::from_millis(25),
+			interrupted: false,
+		}
+	}
+
+	#[test]
+	fn totals() {
+		let total = session().total();
+		assert_eq!((total.results.red, total.results.yellow, total.results.draws), (2, 0, 1));
+		assert_eq!((total.red_first.red, total.yellow_first.red, total.yellow_first.draws), (1, 1, 1));
+		assert_eq!(total.average_length(), 70.0 / 3.0);
+		assert_eq!(total.games_per_second(), 120.0);
+
+		assert_eq!(total.lengths.len(), 43);
+		assert_eq!((total.lengths[7], total.lengths[21], total.lengths[42]), (1, 1, 1));
+		assert_eq!(total.columns, [42, 21, 3, 4]);
+		assert_eq!(total.first_moves.iter().map(|tally| tally.games()).collect::<Vec<_>>(), [1, 1, 0, 1]);
+		assert_eq!((total.first_moves[1].red, total.first_moves[0].draws), (1, 1));
+		assert_eq!((total.lines.horizontal, total.lines.vertical, total.lines.diagonal), (0, 1, 1));
+	}
+
+	#[test]
+	fn csv() {
+		let csv = session().format(Format::Csv);
+		let lines: Vec<&str> = csv.lines().collect();
+		assert_eq!(lines.len(), 4);
+		for line in &lines {
+			assert_eq!(line.split(',').count(), 4 * 3 + 11 + 3 + 43 + 4 + 3);
+		}
+		assert!(lines[0].contains(",first_1_red,first_1_yellow,first_1_draws,"));
+		assert!(lines[0].contains(",horizontal,vertical,diagonal,length_0,"));
+		assert!(lines[0].contains(",length_42,column_1,column_2,column_3,column_4,average_length,"));
+		assert!(lines[1].starts_with("0,2,1,0,1,1,0,0,0,0,1,0,0,1,0,0,0,0,0,0,1,0,0,0,1,0,"));
+		assert!(lines[1].ends_with(",42,0,3,4,24.500,10.000,200.000"));
+		assert!(lines[3].starts_with("total,3,2,0,1,1,0,0,1,0,1,"));
+		assert!(lines[3].ends_with(",42,21,3,4,23.333,25.000,120.000"));
+	}
+
+	#[test]
+	fn json() {
+		let json = session().format(Format::Json);
+		assert!(json.starts_with("{\"seed\": 5, \"interrupted\": false, \"total\": {\"games\": 3, \"red\": 2, \"yellow\": 0, \"draws\": 1, "));
+		assert!(json.contains("\"first_player\": {\"red\": {\"games\": 1, \"red\": 1, \"yellow\": 0, \"draws\": 0}"));
+		assert!(json.contains("\"columns\": [42, 21, 3, 4], "));
+		assert!(json.contains("\"lines\": {\"horizontal\": 0, \"vertical\": 1, \"diagonal\": 1}"));
+		assert!(json.contains("\"elapsed_ms\": 10.000, \"games_per_second\": 200.000}"));
+		assert_eq!(json.matches("\"games_per_second\"").count(), 3);
+	}
+
+	#[test]
+	fn text() {
+		let text = session().to_string();
+		assert!(text.contains("\t  7 moves  33.333% ########################################\n"));
+		assert!(text.contains("\t  8 moves   0.000% \n"));
+		assert!(text.contains("\tcolumn  1 : 60.000%\n"));
+		assert!(text.contains("\tcolumn  2 : 1 game, 100.000% red, 0.000% yellow, 0.000% draw.\n"));
+		assert!(!text.contains("\tcolumn  3 : 0 games"));
+		assert!(text.contains("the winning lines are 0.000% horizontal, 50.000% vertical and 50.000% diagonal."));
+	}
+
+	#[test]
+	fn interrupted_before_the_first_game() {
+		let session = Session {
+			seed: 5,
+			threads: vec![Stats::default(); 2],
+			elapsed: Duration::from_millis(3),
+			interrupted: true,
+		};
+		let text = session.to_string();
+		assert!(text.starts_with("interrupted, only the games already played are counted.
+"));
+		assert!(text.contains("	0.000% victory for the red token.
+"));
+		assert!(text.contains("	0.000% draw.
+"));
+		assert!(!text.contains("NaN"));
+		assert!(!session.format(Format::Json).contains("NaN"));
+		assert!(!session.format(Format::Csv).contains("NaN"));
+	}
+
+	#[test]
+	fn progress() {
+		let progress = Progress {
+			stats: session().total(),
+			games: 12,
+			elapsed: Duration::from_millis(1500),
+		};
+		assert_eq!(progress.remaining(), Some(Duration::from_millis(4500)));
+		assert_eq!(
+			progress.to_string(),
+			"3/12 games (25.0%), 2 games/s, 4s left : red 66.67%, yellow 0.00%, draw 33.33%"
+		);
+		let start = Progress {
+			stats: Stats::default(),
+			games: 12,
+			elapsed: Duration::ZERO,
+		};
+		assert_eq!(start.remaining(), None);
+	}
+}
@@ -0,0 +1,358 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crossterm::style::Color;
+
+use super::board::Token;
+
+// The colors and the symbols of the board on the terminal.
+//
+// Custom themes are read from a file holding one `key value` pair per line,
+// each theme starts with its name and may start from a built-in one:
+//
+//     # comments and blank lines are skipped
+//     theme ocean
+//     base deuteranopia
+//     red #d55e00
+//     yellow #56b4e9
+//     frame #000000
+//     red_symbol X
+//
+// The colors are written `#rrggbb`, with a name like `white` or `dark_grey`,
+// or `default` for the color of the terminal. The keys are the fields of the
+// theme, the text colors follow the colors of the tokens unless they are given.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+	// The colors of the tokens on the board
+	pub red: Color,
+	pub yellow: Color,
+	// The colors of the names of the players in the messages
+	pub red_text: Color,
+	pub yellow_text: Color,
+	pub frame: Color,
+	// The color of the empty cells
+	pub hole: Color,
+	// The background and the color of the column numbers
+	pub header: Color,
+	pub numbers: Color,
+	// The color of the symbols written in the tokens
+	pub mark: Color,
+	// The color of the messages about what went wrong
+	pub error: Color,
+	// The symbols written in the tokens, a space writes nothing
+	pub red_symbol: char,
+	pub yellow_symbol: char,
+}
+
+impl Default for Theme {
+	fn default() -> Theme {
+		Theme::CLASSIC
+	}
+}
+
+impl Theme {
+	pub const CLASSIC: Theme = Theme {
+		red: Color::Rgb { r: 255, g: 0, b: 0 },
+		yellow: Color::Rgb { r: 255, g: 255, b: 50 },
+		red_text: Color::Rgb { r: 255, g: 0, b: 0 },
+		yellow_text: Color::Rgb { r: 255, g: 255, b: 50 },
+		frame: Color::Rgb { r: 0, g: 100, b: 255 },
+		hole: Color::White,
+		header: Color::DarkCyan,
+		numbers: Color::Rgb { r: 255, g: 255, b: 0 },
+		mark: Color::Black,
+		error: Color::Rgb { r: 255, g: 0, b: 0 },
+		red_symbol: ' ',
+		yellow_symbol: ' ',
+	};
+
+	// Pure colors on a black frame, the tokens are told apart by their symbol too
+	pub const HIGH_CONTRAST: Theme = Theme {
+		red: Color::Rgb { r: 220, g: 0, b: 0 },
+		yellow: Color::Rgb { r: 255, g: 230, b: 0 },
+		red_text: Color::Rgb { r: 255, g: 60, b: 60 },
+		yellow_text: Color::Rgb { r: 255, g: 230, b: 0 },
+		frame: Color::Black,
+		hole: Color::White,
+		header: Color::Black,
+		numbers: Color::White,
+		mark: Color::Black,
+		error: Color::Rgb { r: 255, g: 60, b: 60 },
+		red_symbol: 'X',
+		yellow_symbol: 'O',
+	};
+
+	// The orange and the blue of the Okabe-Ito palette, told apart whatever the
+	// sensitivity to red and green
+	pub const DEUTERANOPIA: Theme = Theme {
+		red: Color::Rgb { r: 230, g: 159, b: 0 },
+		yellow: Color::Rgb { r: 0, g: 114, b: 178 },
+		red_text: Color::Rgb { r: 230, g: 159, b: 0 },
+		yellow_text: Color::Rgb { r: 86, g: 180, b: 233 },
+		frame: Color::Rgb { r: 70, g: 70, b: 70 },
+		hole: Color::White,
+		header: Color::Rgb { r: 70, g: 70, b: 70 },
+		numbers: Color::White,
+		mark: Color::Black,
+		// the vermillion of the palette
+		error: Color::Rgb { r: 213, g: 94, b: 0 },
+		red_symbol: ' ',
+		yellow_symbol: ' ',
+	};
+
+	// Shades of grey only, the tokens are told apart by their symbol
+	pub const MONOCHROME: Theme = Theme {
+		red: Color::White,
+		yellow: Color::White,
+		red_text: Color::Reset,
+		yellow_text: Color::Reset,
+		frame: Color::Rgb { r: 90, g: 90, b: 90 },
+		hole: Color::Rgb { r: 200, g: 200, b: 200 },
+		header: Color::Rgb { r: 90, g: 90, b: 90 },
+		numbers: Color::White,
+		mark: Color::Black,
+		error: Color::Reset,
+		red_symbol: 'X',
+		yellow_symbol: 'O',
+	};
+
+	pub const NAMES: &'static [&'static str] = &["classic", "high-contrast", "deuteranopia", "monochrome"];
+
+	pub fn builtin(name: &str) -> Option<Theme> {
+		match name {
+			"classic" => Some(Theme::CLASSIC),
+			"high-contrast" => Some(Theme::HIGH_CONTRAST),
+			"deuteranopia" => Some(Theme::DEUTERANOPIA),
+			"monochrome" => Some(Theme::MONOCHROME),
+			_ => None,
+		}
+	}
+
+	// The theme called `name`, looked for in the themes of `file` first then in
+	// the built-in ones
+	pub fn load(name: &str, file: Option<&Path>) -> Result<Theme, ThemeError> {
+		if let Some(file) = file {
+			let themes = parse_themes(&fs::read_to_string(file)?)?;
+			if let Some((_, theme)) = themes.into_iter().find(|(theme, _)| theme == name) {
+				return Ok(theme);
+			}
+		}
+		Theme::builtin(name).ok_or_else(|| ThemeError::Unknown(name.to_string()))
+	}
+
+	pub fn color(&self, token: Token) -> Color {
+		match token {
+			Token::Red => self.red,
+			Token::Yellow => self.yellow,
+			Token::Empty => self.hole,
+		}
+	}
+
+	pub fn text(&self, token: Token) -> Color {
+		match token {
+			Token::Red => self.red_text,
+			Token::Yellow => self.yellow_text,
+			Token::Empty => Color::Reset,
+		}
+	}
+
+	pub fn symbol(&self, token: Token) -> char {
+		match token {
+			Token::Red => self.red_symbol,
+			Token::Yellow => self.yellow_symbol,
+			Token::Empty => ' ',
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+	Io(io::Error),
+	// No theme has the name
+	Unknown(String),
+	// A line of the file is not a known `key value` pair, with its number
+	InvalidLine(usize, String),
+	InvalidColor(String),
+}
+
+impl fmt::Display for ThemeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ThemeError::Io(err) => write!(f, "{}", err),
+			ThemeError::Unknown(name) => write!(f, "unknown theme '{}', expected {} or a theme of the file", name, Theme::NAMES.join(", ")),
+			ThemeError::InvalidLine(number, line) => write!(f, "unexpected line {} '{}'", number, line),
+			ThemeError::InvalidColor(color) => write!(f, "invalid color '{}'", color),
+		}
+	}
+}
+
+impl std::error::Error for ThemeError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			ThemeError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for ThemeError {
+	fn from(err: io::Error) -> ThemeError {
+		ThemeError::Io(err)
+	}
+}
+
+fn parse_color(color: &str) -> Result<Color, ThemeError> {
+	let invalid = || ThemeError::InvalidColor(color.to_string());
+	if let Some(hex) = color.strip_prefix('#') {
+		if hex.len() != 6 || !hex.is_ascii() {
+			return Err(invalid());
+		}
+		let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+		return Ok(Color::Rgb {
+			r: channel(0)?,
+			g: channel(2)?,
+			b: channel(4)?,
+		});
+	}
+	Ok(match color {
+		"default" => Color::Reset,
+		"black" => Color::Black,
+		"dark_grey" => Color::DarkGrey,
+		"grey" => Color::Grey,
+		"white" => Color::White,
+		"red" => Color::Red,
+		"dark_red" => Color::DarkRed,
+		"green" => Color::Green,
+		"dark_green" => Color::DarkGreen,
+		"yellow" => Color::Yellow,
+		"dark_yellow" => Color::DarkYellow,
+		"blue" => Color::Blue,
+		"dark_blue" => Color::DarkBlue,
+		"magenta" => Color::Magenta,
+		"dark_magenta" => Color::DarkMagenta,
+		"cyan" => Color::Cyan,
+		"dark_cyan" => Color::DarkCyan,
+		_ => return Err(invalid()),
+	})
+}
+
+// The themes of a file with their names, in order
+fn parse_themes(content: &str) -> Result<Vec<(String, Theme)>, ThemeError> {
+	let mut themes: Vec<(String, Theme)> = vec![];
+	// whether the colors of the names were given in the current theme
+	let mut texts = [false, false];
+	for (number, line) in content.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let invalid = || ThemeError::InvalidLine(number + 1, line.to_string());
+		let (key, value) = line.split_once(' ').ok_or_else(invalid)?;
+		let value = value.trim();
+		if key == "theme" {
+			themes.push((value.to_string(), Theme::default()));
+			texts = [false, false];
+			continue;
+		}
+		let (_, theme) = themes.last_mut().ok_or_else(invalid)?;
+		let symbol = || {
+			let mut chars = value.chars();
+			match (chars.next(), chars.next()) {
+				(Some(symbol), None) => Ok(symbol),
+				_ => Err(invalid()),
+			}
+		};
+		match key {
+			"base" => {
+				*theme = Theme::builtin(value).ok_or_else(|| ThemeError::Unknown(value.to_string()))?;
+				texts = [false, false];
+			}
+			"red" => {
+				theme.red = parse_color(value)?;
+				if !texts[0] {
+					theme.red_text = theme.red;
+				}
+			}
+			"yellow" => {
+				theme.yellow = parse_color(value)?;
+				if !texts[1] {
+					theme.yellow_text = theme.yellow;
+				}
+			}
+			"red_text" => {
+				theme.red_text = parse_color(value)?;
+				texts[0] = true;
+			}
+			"yellow_text" => {
+				theme.yellow_text = parse_color(value)?;
+				texts[1] = true;
+			}
+			"frame" => theme.frame = parse_color(value)?,
+			"hole" => theme.hole = parse_color(value)?,
+			"header" => theme.header = parse_color(value)?,
+			"numbers" => theme.numbers = parse_color(value)?,
+			"mark" => theme.mark = parse_color(value)?,
+			"error" => theme.error = parse_color(value)?,
+			"red_symbol" => theme.red_symbol = symbol()?,
+			"yellow_symbol" => theme.yellow_symbol = symbol()?,
+			_ => return Err(invalid()),
+		}
+	}
+	Ok(themes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_color, parse_themes, Color, Theme, ThemeError};
+
+	#[test]
+	fn builtin_themes() {
+		for name in Theme::NAMES {
+			assert!(Theme::builtin(name).is_some());
+		}
+		assert_eq!(Theme::load("classic", None).unwrap(), Theme::default());
+		assert!(matches!(Theme::load("neon", None), Err(ThemeError::Unknown(_))));
+		assert_eq!(parse_color("#0a10ff").unwrap(), Color::Rgb { r: 10, g: 16, b: 255 });
+		assert_eq!(parse_color("dark_grey").unwrap(), Color::DarkGrey);
+		assert!(parse_color("#0a10f").is_err());
+		assert!(parse_color("pink").is_err());
+	}
+
+	#[test]
+	fn custom_themes() {
+		let themes = parse_themes(
+			"# my themes\n\
+			theme ocean\n\
+			red #d55e00\n\
+			yellow_text white\n\
+			yellow #56b4e9\n\
+			red_symbol X\n\
+			\n\
+			theme grey\n\
+			base monochrome\n\
+			frame black\n\
+			error dark_red\n",
+		)
+		.unwrap();
+		assert_eq!(themes.len(), 2);
+		let (name, ocean) = &themes[0];
+		assert_eq!(name, "ocean");
+		assert_eq!(ocean.red, Color::Rgb { r: 213, g: 94, b: 0 });
+		assert_eq!(ocean.red_text, ocean.red);
+		assert_eq!(ocean.yellow_text, Color::White);
+		assert_eq!((ocean.red_symbol, ocean.yellow_symbol), ('X', ' '));
+		assert_eq!(ocean.hole, Theme::CLASSIC.hole);
+		let (_, grey) = &themes[1];
+		assert_eq!(grey.frame, Color::Black);
+		assert_eq!(grey.yellow_symbol, 'O');
+		assert_eq!((grey.error, ocean.error), (Color::DarkRed, Theme::CLASSIC.error));
+
+		assert!(matches!(parse_themes("red #ffffff"), Err(ThemeError::InvalidLine(1, _))));
+		assert!(matches!(parse_themes("theme a\nglow #ffffff"), Err(ThemeError::InvalidLine(2, _))));
+		assert!(matches!(parse_themes("theme a\nred_symbol XO"), Err(ThemeError::InvalidLine(2, _))));
+		assert!(matches!(parse_themes("theme a\nred #fff"), Err(ThemeError::InvalidColor(_))));
+		assert!(matches!(parse_themes("theme a\nbase neon"), Err(ThemeError::Unknown(_))));
+	}
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use super::board::{Board, Token};
+use super::player::{play, Agent};
+use super::random::fan_out;
+use super::state::{Game, Status};
+
+// Results of an agent against another one
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Record {
+	pub wins: u64,
+	pub draws: u64,
+	pub losses: u64,
+}
+
+impl Record {
+	pub fn games(&self) -> u64 {
+		self.wins + self.draws + self.losses
+	}
+
+	// A win is worth one point and a draw half a point
+	pub fn points(&self) -> f64 {
+		self.wins as f64 + self.draws as f64 / 2.0
+	}
+}
+
+impl std::ops::AddAssign for Record {
+	fn add_assign(&mut self, other: Record) {
+		self.wins += other.wins;
+		self.draws += other.draws;
+		self.losses += other.losses;
+	}
+}
+
+// The results of every pairing of a round robin tournament
+pub struct Tournament {
+	pub agents: Vec<Agent>,
+	// `records[i][j]` holds the results of the agent `i` against the agent `j`
+	pub records: Vec<Vec<Record>>,
+}
+
+impl Tournament {
+	// Every results of the agent
+	pub fn total(&self, agent: usize) -> Record {
+		let mut total = Record::default();
+		for &record in &self.records[agent] {
+			total += record;
+		}
+		total
+	}
+
+	// Elo ratings fitted on the results with the Bradley-Terry model, their
+	// average is 0. Each pairing counts one more draw so that the agents who
+	// never lost or never won get a finite rating.
+	pub fn ratings(&self) -> Vec<f64> {
+		let n = self.agents.len();
+		let mut strength = vec![1.0; n];
+		for _ in 0..1000 {
+			let mut next = strength.clone();
+			for i in 0..n {
+				let mut points = 0.0;
+				let mut expected = 0.0;
+				for j in (0..n).filter(|&j| j != i) {
+					let record = self.records[i][j];
+					points += record.points() + 0.5;
+					expected += (record.games() + 1) as f64 / (strength[i] + strength[j]);
+				}
+				if expected > 0.0 {
+					next[i] = points / expected;
+				}
+			}
+			// keeps the geometric mean at 1, a rating of 0
+			let mean = next.iter().map(|s: &f64| s.ln()).sum::<f64>() / n as f64;
+			next.iter_mut().for_each(|s| *s /= mean.exp());
+			let converged = next.iter().zip(&strength).all(|(a, b)| (a - b).abs() < 1e-9);
+			strength = next;
+			if converged {
+				break;
+			}
+		}
+		strength.iter().map(|s| 400.0 * s.log10()).collect()
+	}
+
+	// Half the width of the 95% confidence interval of the rating of the agent,
+	// from the standard error of its average score. One more draw is counted
+	// like for the ratings, a perfect score does not give a certain rating.
+	pub fn margin(&self, agent: usize) -> f64 {
+		let mut total = self.total(agent);
+		total.draws += 1;
+		let games = total.games() as f64;
+		let score = total.points() / games;
+		let variance = (total.wins as f64 * (1.0 - score).powi(2)
+			+ total.draws as f64 * (0.5 - score).powi(2)
+			+ total.losses as f64 * score.powi(2))
+			/ games;
+		// the rating moves by this much for a point of average score
+		let slope = 400.0 / (std::f64::consts::LN_10 * score * (1.0 - score));
+		1.96 * (variance / games).sqrt() * slope
+	}
+}
+
+impl fmt::Display for Tournament {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let names: Vec<String> = self.agents.iter().map(Agent::to_string).collect();
+		let width = names.iter().map(String::len).max().unwrap_or(0).max(9) + 2;
+		let ratings = self.ratings();
+
+		write!(f, "{:width$}", "", width = width)?;
+		for name in &names {
+			write!(f, "{:>width$}", name, width = width)?;
+		}
+		writeln!(f, "{:>12}{:>12}{:>14}", "W-D-L", "Score", "Elo")?;
+		for (i, name) in names.iter().enumerate() {
+			write!(f, "{:width$}", name, width = width)?;
+			for j in 0..names.len() {
+				let record = self.records[i][j];
+				let cell = if i == j {
+					"-".to_string()
+				} else {
+					format!("{}-{}-{}", record.wins, record.draws, record.losses)
+				};
+				write!(f, "{:>width$}", cell, width = width)?;
+			}
+			let total = self.total(i);
+			let score = format!("{}/{}", total.points(), total.games());
+			let rating = format!("{:+.0} ± {:.0}", ratings[i], self.margin(i));
+			writeln!(
+				f,
+				"{:>12}{:>12}{:>14}",
+				format!("{}-{}-{}", total.wins, total.draws, total.losses),
+				score,
+				rating
+			)?;
+		}
+		Ok(())
+	}
+}
+
+// Plays `games` games between each pair of agents from the position of `board`,
+// the agents alternate the red tokens from one game to the next
+pub fn tournament(board: &Board, agents: &[Agent], games: u64, number_thread: u32) -> Tournament {
+	let n = agents.len();
+	let mut matches = vec![];
+	for i in 0..n {
+		for j in i + 1..n {
+			for game in 0..games {
+				matches.push(if game % 2 == 0 { (i, j) } else { (j, i) });
+			}
+		}
+	}
+
+	let played = matches.len() as u64;
+	let board = board.clone();
+	let players = agents.to_vec();
+	let number_thread = number_thread.max(1);
+	let (rx, workers) = fan_out(number_thread, move |thread, tx| {
+		for &(red, yellow) in matches.iter().skip(thread as usize).step_by(number_thread as usize) {
+			let game = play(
+				Game::new(board.clone()),
+				players[red].player(None).as_mut(),
+				players[yellow].player(None).as_mut(),
+			);
+			tx.send((red, yellow, game.status())).unwrap();
+		}
+	});
+
+	let mut records = vec![vec![Record::default(); n]; n];
+	for (red, yellow, status) in &rx {
+		match status {
+			Status::Won(Token::Red) => {
+				records[red][yellow].wins += 1;
+				records[yellow][red].losses += 1;
+			}
+			Status::Won(_) => {
+				records[red][yellow].losses += 1;
+				records[yellow][red].wins += 1;
+			}
+			_ => {
+				records[red][yellow].draws += 1;
+				records[yellow][red].draws += 1;
+			}
+		}
+	}
+	workers.join();
+
+	let tournament = Tournament {
+		agents: agents.to_vec(),
+		records,
+	};
+	let counted: u64 = (0..n).map(|i| tournament.total(i).games()).sum::<u64>() / 2;
+	assert_eq!(counted, played, "some games of the tournament were not counted");
+	tournament
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{tournament, Agent, Board, Record, Tournament};
+
+	#[test]
+	fn every_pairing_is_played() {
+		let agents = [Agent::Random, Agent::Minimax { depth: 1 }, Agent::Minimax { depth: 2 }];
+		let result = tournament(&Board::with_size(5, 4, 3).unwrap(), &agents, 4, 3);
+		for i in 0..3 {
+			assert_eq!(result.total(i).games(), 8);
+			for j in 0..3 {
+				let (record, other) = (result.records[i][j], result.records[j][i]);
+				assert_eq!((record.wins, record.draws), (other.losses, other.draws));
+			}
+		}
+	}
+
+	#[test]
+	fn ratings_follow_the_results() {
+		let record = |wins, draws, losses| Record { wins, draws, losses };
+		let result = Tournament {
+			agents: vec![Agent::Random, Agent::Minimax { depth: 2 }, Agent::Minimax { depth: 4 }],
+			records: vec![
+				vec![record(0, 0, 0), record(2, 0, 8), record(0, 0, 10)],
+				vec![record(8, 0, 2), record(0, 0, 0), record(3, 2, 5)],
+				vec![record(10, 0, 0), record(5, 2, 3), record(0, 0, 0)],
+			],
+		};
+		let ratings = result.ratings();
+		assert!(ratings[0] < ratings[1] && ratings[1] < ratings[2]);
+		assert!(ratings.iter().sum::<f64>().abs() < 1e-6);
+		assert!(result.margin(0) > 0.0 && result.margin(0).is_finite());
+
+		// an even match gives even ratings
+		let even = Tournament {
+			agents: vec![Agent::Random, Agent::Random],
+			records: vec![vec![record(0, 0, 0), record(3, 4, 3)], vec![record(3, 4, 3), record(0, 0, 0)]],
+		};
+		assert!(even.ratings()[0].abs() < 1e-6);
+	}
+}
@@ -0,0 +1,292 @@
+use std::io;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::Duration;
+
+use crossterm::{
+	cursor,
+	event::{
+		self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton,
+		MouseEvent, MouseEventKind,
+	},
+	queue,
+	style::Print,
+	terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use super::board::{MoveError, Token};
+use super::render::{Ansi, Plain, Renderer, MARGIN};
+use super::theme::Theme;
+
+// How the games are shown on the terminal
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Interface {
+	// The time a falling token takes to go down a cell, the tokens are put in
+	// place at once without one
+	pub animation: Option<Duration>,
+	pub theme: Theme,
+	// Writes everything without colors nor escape sequences and keeps to the
+	// prompt instead of the full screen interface, the tokens are told apart by
+	// their symbol
+	pub plain: bool,
+	// The number of hints each human player can ask for, as many as they want without one
+	pub hints: Option<u32>,
+}
+
+impl Default for Interface {
+	fn default() -> Interface {
+		Interface {
+			animation: Some(Duration::from_millis(50)),
+			theme: Theme::default(),
+			plain: false,
+			hints: None,
+		}
+	}
+}
+
+impl Interface {
+	pub fn renderer(&self) -> Box<dyn Renderer> {
+		if self.plain {
+			Box::new(Plain::with_theme(&self.theme))
+		} else {
+			Box::new(Ansi { theme: self.theme })
+		}
+	}
+}
+
+// Whether the full screen interface is shown, the players then read keys
+// instead of lines
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+pub fn is_active() -> bool {
+	ACTIVE.load(Ordering::Relaxed)
+}
+
+// Whether the full screen interface can be shown, both the input and the
+// output must be a terminal
+pub fn available() -> bool {
+	io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+// The terminal in raw mode on the alternate screen with the mouse captured, it
+// is restored when the screen is dropped or when the program panics
+pub struct Screen;
+
+impl Screen {
+	pub fn enter() -> io::Result<Screen> {
+		// the message of a panic is printed once the terminal is restored
+		PANIC_HOOK.call_once(|| {
+			let hook = std::panic::take_hook();
+			std::panic::set_hook(Box::new(move |info| {
+				leave();
+				hook(info);
+			}));
+		});
+		terminal::enable_raw_mode()?;
+		ACTIVE.store(true, Ordering::Relaxed);
+		let mut stdout = io::stdout();
+		queue!(stdout, EnterAlternateScreen, EnableMouseCapture, cursor::Hide)?;
+		stdout.flush()?;
+		Ok(Screen)
+	}
+}
+
+impl Drop for Screen {
+	fn drop(&mut self) {
+		leave();
+	}
+}
+
+// Puts the terminal back in the state it was found, does nothing if the
+// screen is not shown
+pub fn leave() {
+	if ACTIVE.swap(false, Ordering::Relaxed) {
+		let mut stdout = io::stdout();
+		let _ = queue!(stdout, cursor::Show, DisableMouseCapture, LeaveAlternateScreen);
+		let _ = stdout.flush();
+		let _ = terminal::disable_raw_mode();
+	}
+}
+
+// Leaves the screen and ends the program
+pub fn quit() -> ! {
+	leave();
+	std::process::exit(0);
+}
+
+// Replaces the content of the screen with `lines`
+pub fn draw(lines: &[String]) {
+	let mut stdout = io::stdout();
+	queue!(stdout, cursor::MoveTo(0, 0), Clear(ClearType::All)).unwrap();
+	for (row, line) in lines.iter().enumerate() {
+		queue!(stdout, cursor::MoveTo(0, row as u16), Print(line)).unwrap();
+	}
+	stdout.flush().unwrap();
+}
+
+// Writes `cell` at `column` on the line `row` of the screen
+pub fn paint(row: u16, column: u16, cell: &str) {
+	let mut stdout = io::stdout();
+	queue!(stdout, cursor::MoveTo(column, row), Print(cell)).unwrap();
+	stdout.flush().unwrap();
+}
+
+// The line above the board with the token of `token` hovering over `column`
+pub fn hover_line(renderer: &dyn Renderer, token: Token, column: usize) -> String {
+	" ".repeat(MARGIN + 1 + 4 * column) + &renderer.cell(token)
+}
+
+// What a key asks for while a player chooses a column
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+	// Moves the hovering token above the column
+	Select(usize),
+	Drop(usize),
+	Undo,
+	Redo,
+	Save,
+	Hint,
+	Quit,
+	Invalid(MoveError),
+	Ignore,
+}
+
+// The command of `key` when the token hovers over `column` of a board of `cols` columns
+pub fn command(key: KeyEvent, column: usize, cols: usize) -> Command {
+	if key.kind == KeyEventKind::Release {
+		return Command::Ignore;
+	}
+	match key.code {
+		KeyCode::Left => Command::Select(column.saturating_sub(1)),
+		KeyCode::Right => Command::Select((column + 1).min(cols - 1)),
+		KeyCode::Home => Command::Select(0),
+		KeyCode::End => Command::Select(cols - 1),
+		KeyCode::Enter | KeyCode::Char(' ') => Command::Drop(column),
+		KeyCode::Char(digit @ '1'..='9') => {
+			let col = digit.to_digit(10).unwrap() as usize - 1;
+			if col < cols {
+				Command::Drop(col)
+			} else {
+				Command::Invalid(MoveError::OutOfRange)
+			}
+		}
+		KeyCode::Char('u') => Command::Undo,
+		KeyCode::Char('r') => Command::Redo,
+		KeyCode::Char('s') => Command::Save,
+		KeyCode::Char('h') | KeyCode::Char('?') => Command::Hint,
+		KeyCode::Char('q') | KeyCode::Esc => Command::Quit,
+		_ => Command::Ignore,
+	}
+}
+
+// The command of the pointer at `mouse`, the columns of the board can be
+// clicked from the row `top` until the row before `bottom`
+pub fn mouse_command(mouse: MouseEvent, top: u16, bottom: u16, cols: usize) -> Command {
+	if mouse.row < top || mouse.row >= bottom || (mouse.column as usize) <= MARGIN {
+		return Command::Ignore;
+	}
+	// each column takes the 3 characters of its cells and the separation on their right
+	let col = (mouse.column as usize - MARGIN - 1) / 4;
+	if col >= cols {
+		return Command::Ignore;
+	}
+	match mouse.kind {
+		MouseEventKind::Moved | MouseEventKind::Drag(_) => Command::Select(col),
+		MouseEventKind::Down(MouseButton::Left) => Command::Drop(col),
+		_ => Command::Ignore,
+	}
+}
+
+// Waits for the next key pressed or the next move of the mouse, quits on Ctrl-C
+pub fn read_event() -> Event {
+	loop {
+		match event::read().expect("Error reading user input.") {
+			Event::Key(key) if key.kind == KeyEventKind::Release => {}
+			Event::Key(key) if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) => quit(),
+			event => return event,
+		}
+	}
+}
+
+// Waits for the next key pressed
+pub fn read_key() -> KeyEvent {
+	loop {
+		if let Event::Key(key) = read_event() {
+			return key;
+		}
+	}
+}
+
+// Forgets the keys pressed and the clicks made until now, they were meant for
+// another screen
+pub fn discard_events() {
+	while event::poll(std::time::Duration::ZERO).unwrap_or(false) {
+		let _ = event::read();
+	}
+}
+
+// Asks for a line of text below `lines`, none when Esc is pressed
+pub fn read_line(lines: &[String], prompt: &str) -> Option<String> {
+	let mut text = String::new();
+	loop {
+		let mut screen = lines.to_vec();
+		screen.push(format!("{}{}_", prompt, text));
+		draw(&screen);
+		match read_key().code {
+			KeyCode::Enter => return Some(text),
+			KeyCode::Esc => return None,
+			KeyCode::Backspace => {
+				text.pop();
+			}
+			KeyCode::Char(c) => text.push(c),
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		command, mouse_command, Command, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+		MoveError,
+	};
+
+	#[test]
+	fn keys_choose_the_column() {
+		let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+		assert_eq!(command(key(KeyCode::Left), 3, 7), Command::Select(2));
+		assert_eq!(command(key(KeyCode::Left), 0, 7), Command::Select(0));
+		assert_eq!(command(key(KeyCode::Right), 6, 7), Command::Select(6));
+		assert_eq!(command(key(KeyCode::Enter), 4, 7), Command::Drop(4));
+		assert_eq!(command(key(KeyCode::Char(' ')), 4, 7), Command::Drop(4));
+		assert_eq!(command(key(KeyCode::Char('1')), 4, 7), Command::Drop(0));
+		assert_eq!(command(key(KeyCode::Char('8')), 4, 7), Command::Invalid(MoveError::OutOfRange));
+		assert_eq!(command(key(KeyCode::Char('u')), 4, 7), Command::Undo);
+		assert_eq!(command(key(KeyCode::Char('?')), 4, 7), Command::Hint);
+		assert_eq!(command(key(KeyCode::Esc), 4, 7), Command::Quit);
+		assert_eq!(command(key(KeyCode::Char('x')), 4, 7), Command::Ignore);
+	}
+
+	#[test]
+	fn clicks_choose_the_column() {
+		let mouse = |kind, column, row| MouseEvent {
+			kind,
+			column,
+			row,
+			modifiers: KeyModifiers::NONE,
+		};
+		let click = MouseEventKind::Down(MouseButton::Left);
+		assert_eq!(mouse_command(mouse(click, 9, 5), 2, 18, 7), Command::Drop(0));
+		assert_eq!(mouse_command(mouse(click, 12, 5), 2, 18, 7), Command::Drop(0));
+		assert_eq!(mouse_command(mouse(click, 13, 2), 2, 18, 7), Command::Drop(1));
+		assert_eq!(mouse_command(mouse(MouseEventKind::Moved, 35, 17), 2, 18, 7), Command::Select(6));
+		// outside of the board
+		assert_eq!(mouse_command(mouse(click, 8, 5), 2, 18, 7), Command::Ignore);
+		assert_eq!(mouse_command(mouse(click, 37, 5), 2, 18, 7), Command::Ignore);
+		assert_eq!(mouse_command(mouse(click, 13, 1), 2, 18, 7), Command::Ignore);
+		assert_eq!(mouse_command(mouse(click, 13, 18), 2, 18, 7), Command::Ignore);
+		assert_eq!(mouse_command(mouse(MouseEventKind::Down(MouseButton::Right), 13, 5), 2, 18, 7), Command::Ignore);
+	}
+}
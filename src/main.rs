@@ -1,10 +1,23 @@
+#![allow(special_module_name)]
+
+use std::io;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossterm::terminal::{Clear, ClearType};
+use structopt::clap;
 use structopt::StructOpt;
 
 use lib::*;
 
 mod lib;
 
-#[derive(StructOpt, Debug)]
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum Shell {
 	Bash,
 	Fish,
@@ -12,6 +25,161 @@ enum Shell {
 	Zsh,
 }
 
+impl Shell {
+	const NAMES: &'static [&'static str] = &["bash", "fish", "powershell", "zsh"];
+}
+
+impl FromStr for Shell {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Shell, String> {
+		match name.to_lowercase().as_str() {
+			"bash" => Ok(Shell::Bash),
+			"fish" => Ok(Shell::Fish),
+			"powershell" => Ok(Shell::PowerShell),
+			"zsh" => Ok(Shell::Zsh),
+			_ => Err(format!("unknown shell {}", name)),
+		}
+	}
+}
+
+impl From<Shell> for clap::Shell {
+	fn from(shell: Shell) -> clap::Shell {
+		match shell {
+			Shell::Bash => clap::Shell::Bash,
+			Shell::Fish => clap::Shell::Fish,
+			Shell::PowerShell => clap::Shell::PowerShell,
+			Shell::Zsh => clap::Shell::Zsh,
+		}
+	}
+}
+
+#[derive(StructOpt, Debug)]
+struct Size {
+	#[structopt(long, default_value = "7")]
+	/// The number of columns of the board
+	cols: usize,
+
+	#[structopt(long, default_value = "6")]
+	/// The number of rows of the board
+	rows: usize,
+
+	#[structopt(long, default_value = "4")]
+	/// The number of tokens to line up to win
+	connect: usize,
+}
+
+impl Size {
+	fn board(&self) -> Board {
+		match Board::with_size(self.cols, self.rows, self.connect) {
+			Ok(board) => board,
+			Err(err) => {
+				eprintln!("Invalid board, {}.", err);
+				std::process::exit(1);
+			}
+		}
+	}
+}
+
+#[derive(StructOpt, Debug)]
+struct MctsSettings {
+	#[structopt(long)]
+	/// The number of random games played by the mcts engine for each move [default: 10000]
+	iterations: Option<u32>,
+
+	#[structopt(long)]
+	/// The time in milliseconds after which the mcts engine stops playing random games
+	time: Option<u64>,
+
+	#[structopt(long)]
+	/// How much the mcts engine explores the less played moves [default: 1.414]
+	exploration: Option<f64>,
+}
+
+impl MctsSettings {
+	fn mcts(&self) -> Mcts {
+		let default = Mcts::default();
+		if self.iterations == Some(0) {
+			eprintln!("Invalid mcts settings, the number of iterations must be at least 1.");
+			std::process::exit(1);
+		}
+		Mcts {
+			iterations: self.iterations.unwrap_or(default.iterations),
+			time: self.time.map(Duration::from_millis),
+			exploration: self.exploration.unwrap_or(default.exploration),
+		}
+	}
+}
+
+#[derive(StructOpt, Debug)]
+struct InterfaceSettings {
+	#[structopt(long)]
+	/// Put the tokens in place at once instead of letting them fall down their column
+	no_animation: bool,
+
+	#[structopt(long, default_value = "50")]
+	/// The time in milliseconds a falling token takes to go down a cell
+	animation_delay: u64,
+
+	#[structopt(long, default_value = "classic")]
+	/// The colors of the board: classic, high-contrast, deuteranopia, monochrome or a theme of the theme file
+	theme: String,
+
+	#[structopt(long, parse(from_os_str))]
+	/// The file of the custom themes [default: ~/.config/connect_four/themes if it exists]
+	theme_file: Option<PathBuf>,
+
+	#[structopt(long)]
+	/// Write the board with symbols, without colors nor full screen interface, the default when the output is not a terminal or NO_COLOR is set
+	plain: bool,
+
+	#[structopt(long)]
+	/// The number of hints (`hint` or `?` at the prompt) each human player can ask for [default: no limit]
+	hints: Option<u32>,
+}
+
+impl InterfaceSettings {
+	fn interface(&self) -> Interface {
+		let theme_file = self.theme_file.clone().or_else(default_theme_file);
+		let theme = match Theme::load(&self.theme, theme_file.as_deref()) {
+			Ok(theme) => theme,
+			Err(err) => {
+				eprintln!("Invalid theme, {}.", err);
+				std::process::exit(1);
+			}
+		};
+		Interface {
+			animation: if self.no_animation {
+				None
+			} else {
+				Some(Duration::from_millis(self.animation_delay))
+			},
+			theme,
+			plain: self.plain || !io::stdout().is_terminal() || no_color(),
+			hints: self.hints,
+		}
+	}
+}
+
+// Whether the user asked for no colors with a non empty NO_COLOR variable
+fn no_color() -> bool {
+	std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+// The theme file in the configuration directory of the user, if there is one
+fn default_theme_file() -> Option<PathBuf> {
+	let config = match std::env::var_os("XDG_CONFIG_HOME") {
+		Some(config) if !config.is_empty() => PathBuf::from(config),
+		_ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+	};
+	let file = config.join("connect_four").join("themes");
+	if file.is_file() {
+		Some(file)
+	} else {
+		None
+	}
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = "Connect four")]
 /// A game where you need to connect four tokens to win, but you can only place tokens in a column.
@@ -19,11 +187,73 @@ enum Shell {
 /// When you place a token it will fall down to the lowest available space.
 enum Cmd {
 	/// Play a game against a fellow human
-	Human,
-	/// Play a game against the computer (random stroke)
-	Computer,
+	Human {
+		#[structopt(flatten)]
+		size: Size,
+
+		#[structopt(long, parse(from_os_str))]
+		/// Resume a game saved with `save <file>` at the prompt
+		resume: Option<PathBuf>,
+
+		#[structopt(flatten)]
+		interface: InterfaceSettings,
+	},
+	/// Play a game against the computer
+	Computer {
+		#[structopt(flatten)]
+		size: Size,
+
+		#[structopt(short, long, default_value = "5")]
+		/// The number of moves the computer looks ahead, 0 makes it play random strokes
+		depth: u32,
+
+		#[structopt(long, default_value = "minimax", possible_values = &["minimax", "mcts"])]
+		/// How the computer chooses its moves, by searching ahead (minimax) or with random games (mcts)
+		engine: String,
+
+		#[structopt(flatten)]
+		mcts: MctsSettings,
+
+		#[structopt(long, parse(from_os_str))]
+		/// Resume a game saved with `save <file>` at the prompt
+		resume: Option<PathBuf>,
+
+		#[structopt(flatten)]
+		interface: InterfaceSettings,
+
+		#[structopt(long)]
+		/// Seed the random moves of the computer to replay the same game
+		seed: Option<u64>,
+	},
+	/// Play a game between any two players
+	Play {
+		#[structopt(flatten)]
+		size: Size,
+
+		#[structopt(long, default_value = "human")]
+		/// The player of the red tokens: human, random, minimax[:depth] or mcts[:iterations[:exploration[:time]]]
+		red: Agent,
+
+		#[structopt(long, default_value = "minimax")]
+		/// The player of the yellow tokens, written like the red one
+		yellow: Agent,
+
+		#[structopt(long, parse(from_os_str))]
+		/// Resume a game saved with `save <file>` at the prompt
+		resume: Option<PathBuf>,
+
+		#[structopt(flatten)]
+		interface: InterfaceSettings,
+
+		#[structopt(long)]
+		/// Seed the random moves of the computer to replay the same game
+		seed: Option<u64>,
+	},
 	/// Play games with the computer against himself (random stroke)
 	Random {
+		#[structopt(flatten)]
+		size: Size,
+
 		#[structopt(short, long, default_value = "1")]
 		/// The number of threads to use
 		threads: u32,
@@ -31,16 +261,174 @@ enum Cmd {
 		#[structopt(short, long, default_value = "100")]
 		/// The number of rounds to play per thread
 		rounds: u64,
+
+		#[structopt(long)]
+		/// Seed the random moves to replay the same games, each thread derives its own seed from it
+		seed: Option<u64>,
+
+		#[structopt(long, default_value = "text", possible_values = &["text", "json", "csv"])]
+		/// How the results are printed
+		format: Format,
+	},
+	/// Play every pair of players against each other and rate them
+	Tournament {
+		#[structopt(flatten)]
+		size: Size,
+
+		#[structopt(required = true, min_values = 2)]
+		/// The players: random, minimax[:depth] or mcts[:iterations[:exploration[:time]]]
+		agents: Vec<Agent>,
+
+		#[structopt(short, long, default_value = "10")]
+		/// The number of games of each pairing, the players take the red tokens in turn
+		games: u64,
+
+		#[structopt(short, long, default_value = "1")]
+		/// The number of threads to use
+		threads: u32,
+	},
+	/// Find the result of a position under perfect play
+	Solve {
+		#[structopt(flatten)]
+		size: Size,
+
+		#[structopt(default_value = "")]
+		/// The columns played since the start of the game (starting from 1, then a, b, c... after 9, or numbers separated by commas
+		/// beyond 35 columns), red plays first,
+		/// or the grid of the board from the top like "7/7/7/7/3y3/2rrr2 r" followed by the player about to play
+		position: String,
+	},
+	/// Print the completion script of a shell
+	Completions {
+		#[structopt(possible_values = Shell::NAMES, case_insensitive = true)]
+		/// The shell to generate the script for
+		shell: Shell,
 	},
 }
 
+// The saved game is resumed with the players it was played with, whatever the subcommand
+fn resume(file: PathBuf, seed: Option<u64>, interface: Interface) {
+	match SavedGame::load(&file) {
+		Ok(SavedGame { red, yellow, game }) => interactive_game(game, red, yellow, seed, interface),
+		Err(err) => {
+			eprintln!("Cannot resume the game from {}, {}.", file.display(), err);
+			std::process::exit(1);
+		}
+	}
+}
+
 fn main() {
 	let cmd: Cmd = Cmd::from_args();
 	match cmd {
-		Cmd::Human => against_another_player(),
-		Cmd::Computer => against_computer(),
-		Cmd::Random { threads, rounds } => {
-			game_session(threads, rounds, true);
+		Cmd::Human {
+			resume: Some(file),
+			interface,
+			..
+		} => resume(file, None, interface.interface()),
+		Cmd::Computer {
+			resume: Some(file),
+			seed,
+			interface,
+			..
+		}
+		| Cmd::Play {
+			resume: Some(file),
+			seed,
+			interface,
+			..
+		} => resume(file, seed, interface.interface()),
+		Cmd::Human { size, interface, .. } => interactive_game(
+			Game::new(size.board()),
+			Agent::Human,
+			Agent::Human,
+			None,
+			interface.interface(),
+		),
+		Cmd::Computer {
+			size,
+			depth,
+			engine,
+			mcts,
+			seed,
+			interface,
+			..
+		} => {
+			let computer = match engine.as_str() {
+				"mcts" => Agent::Mcts(mcts.mcts()),
+				_ if depth == 0 => Agent::Random,
+				_ => Agent::Minimax { depth },
+			};
+			interactive_game(Game::new(size.board()), Agent::Human, computer, seed, interface.interface())
+		}
+		Cmd::Play {
+			size,
+			red,
+			yellow,
+			seed,
+			interface,
+			..
+		} => interactive_game(Game::new(size.board()), red, yellow, seed, interface.interface()),
+		Cmd::Random {
+			size,
+			threads,
+			rounds,
+			seed,
+			format,
+		} => {
+			// the first Ctrl-C stops the threads after their current game, the second one quits at once
+			let stop = Arc::new(AtomicBool::new(false));
+			let flag = Arc::clone(&stop);
+			ctrlc::set_handler(move || {
+				if flag.swap(true, Ordering::Relaxed) {
+					std::process::exit(130);
+				}
+			})
+			.expect("cannot handle Ctrl-C");
+
+			// the progress is written over the same line of the terminal
+			let terminal = io::stderr().is_terminal();
+			let session = game_session(&size.board(), threads, rounds, true, seed, stop, |progress| {
+				if terminal {
+					eprint!("\r{}{}", Clear(ClearType::CurrentLine), progress);
+				}
+			});
+			if terminal {
+				eprint!("\r{}", Clear(ClearType::CurrentLine));
+			}
+			print!("{}", session.format(format));
+		}
+		Cmd::Tournament {
+			size,
+			agents,
+			games,
+			threads,
+		} => {
+			if agents.contains(&Agent::Human) {
+				eprintln!("Invalid players, a human cannot take part in a tournament.");
+				std::process::exit(1);
+			}
+			print!("{}", tournament(&size.board(), &agents, games, threads));
+		}
+		Cmd::Solve { size, position } => match solve(size.board(), &position) {
+			Ok(solution) => {
+				let player = if solution.player == Token::Red { "red" } else { "yellow" };
+				let moves = match solution.moves {
+					1 => "1 move".to_string(),
+					moves => format!("{} moves", moves),
+				};
+				match solution.outcome {
+					Outcome::Win => println!("The {} player wins in {}.", player, moves),
+					Outcome::Loss => println!("The {} player loses in {}.", player, moves),
+					Outcome::Draw => println!("The game ends in a draw after {}.", moves),
+				}
+			}
+			Err(err) => {
+				eprintln!("Invalid position, {}.", err);
+				std::process::exit(1);
+			}
+		},
+		Cmd::Completions { shell } => {
+			Cmd::clap().gen_completions_to(env!("CARGO_PKG_NAME"), shell.into(), &mut io::stdout());
 		}
 	}
 }
\ No newline at end of file